@@ -0,0 +1,179 @@
+use crate::{Args, Error, ErrorKind};
+use std::{
+    f32::consts::TAU,
+    fmt,
+    path::Path,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// Camera path of `--animate`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Animation {
+    /// One full turn around the camera target over all frames
+    Orbit,
+}
+
+impl Animation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Animation::Orbit => "orbit",
+        }
+    }
+
+    fn camera(self, start: raytracer::Camera, frame: u32, frames: u32) -> raytracer::Camera {
+        match self {
+            Animation::Orbit => raytracer::Camera {
+                yaw: start.yaw + TAU * frame as f32 / frames as f32,
+                ..start
+            },
+        }
+    }
+}
+
+impl fmt::Display for Animation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Animation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "orbit" => Ok(Animation::Orbit),
+            _ => Err(format!("unknown animation `{s}`, expected orbit")),
+        }
+    }
+}
+
+/// Rewritten in the output directory after every saved frame
+const PROGRESS_FILE: &str = "animation.toml";
+
+/// What `--resume-animation` continues from
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Progress {
+    animation: Animation,
+    frames: u32,
+    /// Base seed, as a string since TOML integers are signed
+    seed: String,
+    /// Frames saved so far
+    completed: u32,
+}
+
+/// Renders `args.frames` images headless, each accumulated from scratch
+///
+/// Every frame is traced with its own seed derived from the base one, so a sequence
+/// is reproducible and resuming it yields the same images as an uninterrupted run.
+/// Ctrl-C stops after the frame being rendered is saved, pressing it again aborts.
+pub fn run(args: Args, world: &raytracer::World, animation: Animation) -> Result<(), Error> {
+    let dir = args.output_dir.clone().expect("clap requires --output-dir");
+    let frames = args.frames;
+    let progress_path = dir.join(PROGRESS_FILE);
+    std::fs::create_dir_all(&dir).map_err(|e| {
+        Error::new(
+            ErrorKind::Output,
+            format_args!("can't create `{}`: {e}", dir.display()),
+        )
+    })?;
+
+    let resumed = match args.resume_animation {
+        true => Some(read_progress(&progress_path, animation, frames)?),
+        false => None,
+    };
+    let (exposure, tonemap) = (args.exposure, args.tonemap);
+    let mut app = raytracer::HeadlessApp::new(args.into(), world)?;
+    let (seed, first) = match resumed {
+        Some((seed, completed)) => (seed, completed),
+        None => (app.seed(), 0),
+    };
+    if first >= frames {
+        println!("All {frames} frames are already rendered");
+        return Ok(());
+    }
+
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let handler_flag = interrupted.clone();
+    let handler = ctrlc::set_handler(move || {
+        if handler_flag.swap(true, Ordering::Relaxed) {
+            std::process::exit(ErrorKind::Interrupted.exit_code());
+        }
+        eprintln!("Interrupted, finishing the current frame, press Ctrl-C again to abort");
+    });
+    if let Err(e) = handler {
+        log::warn!("Can't handle Ctrl-C, interrupting loses the current frame: {e}");
+    }
+
+    let start = app.camera();
+    for frame in first..frames {
+        app.set_camera(animation.camera(start, frame, frames));
+        app.reseed(frame_seed(seed, frame));
+        while !app.is_converged() {
+            app.redraw();
+        }
+        let mut image = app
+            .capture()
+            .ok_or_else(|| Error::new(ErrorKind::DeviceFailed, "failed to read back the image"))?;
+        image.tonemap(exposure, tonemap);
+
+        let path = dir.join(format!("frame_{:04}.png", frame + 1));
+        write(&path, image.to_png())?;
+        let progress = Progress {
+            animation,
+            frames,
+            seed: seed.to_string(),
+            completed: frame + 1,
+        };
+        write(
+            &progress_path,
+            toml::to_string(&progress).expect("serializing animation progress"),
+        )?;
+        println!("Saved {} ({}/{frames})", path.display(), frame + 1);
+
+        if interrupted.load(Ordering::Relaxed) && frame + 1 < frames {
+            return Err(Error::new(
+                ErrorKind::Interrupted,
+                format_args!("stopped after {} of {frames} frames", frame + 1),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the base seed and the number of completed frames
+fn read_progress(path: &Path, animation: Animation, frames: u32) -> Result<(u64, u32), Error> {
+    let error = |e: &dyn fmt::Display| {
+        Error::new(
+            ErrorKind::Output,
+            format_args!("can't resume from `{}`: {e}", path.display()),
+        )
+    };
+    let source = std::fs::read_to_string(path).map_err(|e| error(&e))?;
+    let progress: Progress = toml::from_str(&source).map_err(|e| error(&e))?;
+    if progress.animation != animation || progress.frames != frames {
+        return Err(error(&format_args!(
+            "it's for {} frames of `{}`, not {frames} of `{animation}`",
+            progress.frames, progress.animation
+        )));
+    }
+    let seed = progress.seed.parse().map_err(|e| error(&e))?;
+    Ok((seed, progress.completed))
+}
+
+fn frame_seed(seed: u64, frame: u32) -> u64 {
+    seed ^ u64::from(frame).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+}
+
+fn write(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), Error> {
+    std::fs::write(path, contents).map_err(|e| {
+        Error::new(
+            ErrorKind::Output,
+            format_args!("can't write `{}`: {e}", path.display()),
+        )
+    })
+}
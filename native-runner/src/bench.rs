@@ -0,0 +1,93 @@
+use crate::{Args, Error, ErrorKind};
+use std::{path::Path, time::Instant};
+
+/// Summary of `--bench`, also written by `--bench-json`
+#[derive(Debug, serde::Serialize)]
+pub struct BenchReport {
+    pub adapter: String,
+    pub backend: String,
+    pub width: u32,
+    pub height: u32,
+    pub frames: u32,
+    pub samples_per_frame: u32,
+    /// Samples per pixel per second
+    pub samples_per_second: f64,
+    /// Samples of all pixels per second
+    pub pixel_samples_per_second: f64,
+    pub frame_time_mean_ms: f64,
+    pub frame_time_min_ms: f64,
+    pub frame_time_max_ms: f64,
+    pub frame_time_p95_ms: f64,
+}
+
+/// Accumulates `frames` without a window, so neither vsync nor the blit is measured
+///
+/// Every frame waits for the GPU to finish. A warm-up frame, which compiles
+/// pipelines lazily on some backends, isn't counted.
+pub fn run(mut args: Args, world: &raytracer::World, frames: u32) -> Result<BenchReport, Error> {
+    args.max_samples = None;
+    let samples_per_frame = args.samples_per_frame;
+    let mut app = raytracer::HeadlessApp::new(args.into(), world)?;
+    app.redraw();
+
+    let mut frame_times: Vec<f64> = (0..frames)
+        .map(|_| {
+            let started = Instant::now();
+            app.redraw();
+            started.elapsed().as_secs_f64() * 1000.0
+        })
+        .collect();
+    frame_times.sort_by(f64::total_cmp);
+
+    let progress = app.progress();
+    let backend_info = app.backend_info();
+    let total_secs = frame_times.iter().sum::<f64>() / 1000.0;
+    let samples_per_second = f64::from(frames) * f64::from(samples_per_frame) / total_secs;
+    let p95 = (frame_times.len() * 95).div_ceil(100).max(1) - 1;
+    Ok(BenchReport {
+        adapter: backend_info.name.clone(),
+        backend: backend_info.backend.clone(),
+        width: progress.width,
+        height: progress.height,
+        frames,
+        samples_per_frame,
+        samples_per_second,
+        pixel_samples_per_second: samples_per_second
+            * f64::from(progress.width)
+            * f64::from(progress.height),
+        frame_time_mean_ms: total_secs * 1000.0 / f64::from(frames),
+        frame_time_min_ms: frame_times[0],
+        frame_time_max_ms: frame_times[frame_times.len() - 1],
+        frame_time_p95_ms: frame_times[p95],
+    })
+}
+
+impl BenchReport {
+    /// One `key: value` line per field
+    pub fn print(&self) {
+        println!("adapter: {}", self.adapter);
+        println!("backend: {}", self.backend);
+        println!("resolution: {}x{}", self.width, self.height);
+        println!("frames: {}", self.frames);
+        println!("samples_per_frame: {}", self.samples_per_frame);
+        println!("samples_per_second: {:.2}", self.samples_per_second);
+        println!(
+            "pixel_samples_per_second: {:.0}",
+            self.pixel_samples_per_second
+        );
+        println!("frame_time_mean_ms: {:.3}", self.frame_time_mean_ms);
+        println!("frame_time_min_ms: {:.3}", self.frame_time_min_ms);
+        println!("frame_time_max_ms: {:.3}", self.frame_time_max_ms);
+        println!("frame_time_p95_ms: {:.3}", self.frame_time_p95_ms);
+    }
+
+    pub fn write_json(&self, path: &Path) -> Result<(), Error> {
+        let json = serde_json::to_string_pretty(self).expect("serializing bench report");
+        std::fs::write(path, json + "\n").map_err(|e| {
+            Error::new(
+                ErrorKind::Output,
+                format_args!("can't write `{}`: {e}", path.display()),
+            )
+        })
+    }
+}
@@ -0,0 +1,117 @@
+use crate::{Args, Error, ErrorKind};
+use clap::{CommandFactory as _, Parser as _};
+use std::{ffi::OsString, fmt, path::Path};
+
+/// Loaded from the working directory if `--config` isn't given
+pub const DEFAULT_CONFIG: &str = "myraytracer.toml";
+
+/// Arguments that only make sense on the command line
+const CLI_ONLY: [&str; 5] = [
+    "config",
+    "print_config",
+    "list_adapters",
+    "print_scene",
+    "verbose",
+];
+
+/// Applies the config file below the command line flags
+///
+/// Config entries are turned into flags preceding the actual ones, so clap
+/// validates them and explicit flags override them. The command line alone is
+/// already valid, so clap errors are reported as config errors.
+pub fn apply(args: Args) -> Result<Args, Error> {
+    let path = match &args.config {
+        Some(path) => path.clone(),
+        None if Path::new(DEFAULT_CONFIG).is_file() => DEFAULT_CONFIG.into(),
+        None => return Ok(args),
+    };
+    let flags = load(&path)?;
+    let mut argv = std::env::args_os();
+    let program = argv.next().unwrap_or_else(|| "native-runner".into());
+    Args::try_parse_from(std::iter::once(program).chain(flags).chain(argv)).map_err(|e| {
+        // Only the first line, without the usage and the `--help` suggestion
+        let message = e.to_string();
+        let message = message.lines().next().unwrap_or_default();
+        Error::new(
+            ErrorKind::Config,
+            format_args!(
+                "invalid config `{}`: {}",
+                path.display(),
+                message.strip_prefix("error: ").unwrap_or(message)
+            ),
+        )
+    })
+}
+
+/// Converts entries into flags, unknown keys are warned about so configs survive version skew
+fn load(path: &Path) -> Result<Vec<OsString>, Error> {
+    let source = std::fs::read_to_string(path).map_err(|e| {
+        Error::new(
+            ErrorKind::Config,
+            format_args!("can't read config `{}`: {e}", path.display()),
+        )
+    })?;
+    let table: toml::Table = source.parse().map_err(|e| {
+        Error::new(
+            ErrorKind::Config,
+            format_args!("can't parse config `{}`: {e}", path.display()),
+        )
+    })?;
+
+    let command = Args::command();
+    let mut flags = Vec::new();
+    for (key, value) in table {
+        let long = command
+            .get_arguments()
+            .filter(|arg| arg.get_id() == key.as_str() && !CLI_ONLY.contains(&key.as_str()))
+            .find_map(|arg| arg.get_long());
+        let Some(long) = long else {
+            log::warn!("Ignoring unknown key `{key}` in `{}`", path.display());
+            continue;
+        };
+        let value = match value {
+            toml::Value::Boolean(true) => {
+                flags.push(format!("--{long}").into());
+                continue;
+            }
+            toml::Value::Boolean(false) => continue,
+            toml::Value::String(s) => s,
+            toml::Value::Integer(x) => x.to_string(),
+            toml::Value::Float(x) => x.to_string(),
+            _ => {
+                log::warn!(
+                    "Ignoring `{key}` in `{}`, expected a string, number or boolean",
+                    path.display()
+                );
+                continue;
+            }
+        };
+        // Joined, as negative values would be taken for flags otherwise
+        flags.push(format!("--{long}={value}").into());
+    }
+    Ok(flags)
+}
+
+/// For `--print-config`, skipping command line only and run mode arguments
+pub fn to_string(args: &Args) -> String {
+    toml::to_string(args).expect("serializing config")
+}
+
+/// Writes values with their command line spelling
+pub fn serialize_display<S: serde::Serializer>(
+    value: &impl fmt::Display,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+/// TOML integers are signed, so seeds are written as strings
+pub fn serialize_seed<S: serde::Serializer>(
+    seed: &Option<u64>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match seed {
+        Some(seed) => serializer.collect_str(seed),
+        None => serializer.serialize_none(),
+    }
+}
@@ -0,0 +1,101 @@
+use raytracer::{InitError, InitErrorKind};
+use std::fmt;
+
+/// Reason why native-runner failed, reported as a single line and an exit code
+#[derive(Debug)]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+/// Failure category, its discriminant is the process exit code
+///
+/// Starts from 3, as clap exits with 2 on usage errors. Keep [`EXIT_CODES`] in sync.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    NoAdapter = 3,
+    DeviceFailed = 4,
+    SurfaceFailed = 5,
+    WindowFailed = 6,
+    EventLoop = 7,
+    Scene = 8,
+    Output = 9,
+    /// `--verify-reproducible` got different images
+    NotReproducible = 10,
+    Config = 11,
+    /// Ctrl-C stopped `--animate`
+    Interrupted = 130,
+}
+
+/// Listed at the end of `--help`
+pub const EXIT_CODES: &str = "\
+Exit codes:
+  0   success
+  2   invalid arguments
+  3   no suitable GPU adapter
+  4   GPU device request failed
+  5   adapter can't present to the window
+  6   window creation failed
+  7   event loop failed
+  8   scene can't be loaded
+  9   output can't be written
+  10  renders with the same seed differ
+  11  config file can't be read or parsed
+  130 interrupted by Ctrl-C
+  101 internal error, rerun with --verbose for a backtrace";
+
+impl ErrorKind {
+    pub fn exit_code(self) -> i32 {
+        self as i32
+    }
+
+    /// What the user may try next
+    pub fn hint(self) -> Option<&'static str> {
+        match self {
+            ErrorKind::NoAdapter => Some(
+                "install Vulkan, Metal or DX12 drivers or try `--backend gl`, \
+                 `--list-adapters` shows what's available",
+            ),
+            ErrorKind::DeviceFailed => Some("try another `--adapter` or `--backend gl`"),
+            ErrorKind::SurfaceFailed => Some(
+                "the adapter can't present to the window, try another `--adapter` \
+                 or render headless with `--output`",
+            ),
+            ErrorKind::WindowFailed | ErrorKind::EventLoop => {
+                Some("check that a display is available or render headless with `--output`")
+            }
+            ErrorKind::Scene
+            | ErrorKind::Output
+            | ErrorKind::NotReproducible
+            | ErrorKind::Config => None,
+            ErrorKind::Interrupted => Some("rerun with --resume-animation to continue"),
+        }
+    }
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, message: impl fmt::Display) -> Self {
+        Error {
+            kind,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<InitError> for Error {
+    fn from(e: InitError) -> Self {
+        let kind = match e.kind {
+            InitErrorKind::NoAdapter => ErrorKind::NoAdapter,
+            InitErrorKind::DeviceFailed => ErrorKind::DeviceFailed,
+            InitErrorKind::SurfaceFailed => ErrorKind::SurfaceFailed,
+            InitErrorKind::WindowFailed | InitErrorKind::Closed => ErrorKind::WindowFailed,
+        };
+        Error::new(kind, e.message)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
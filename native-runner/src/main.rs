@@ -1,33 +1,340 @@
 use clap::Parser as _;
+use error::{Error, ErrorKind};
 use raytracer::{winit::event_loop::EventLoop, App};
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+mod animation;
+mod bench;
+mod config;
+mod error;
 
 fn main() {
+    let args = Args::parse();
+    if args.verbose && std::env::var_os("RUST_BACKTRACE").is_none() {
+        std::env::set_var("RUST_BACKTRACE", "1");
+    }
     // TODO: use tracing?
     env_logger::builder()
-        .filter_level(log::LevelFilter::Info)
+        .filter_level(match args.verbose {
+            true => log::LevelFilter::Debug,
+            false => log::LevelFilter::Info,
+        })
         .parse_default_env()
         .init();
-    let args = Args::parse();
-    let event_loop = EventLoop::with_user_event()
-        .build()
-        .expect("failed to build an event loop");
-    let mut app = App::new(&event_loop, args.into(), raytracer::PlatformArgs {});
-    event_loop.run_app(&mut app).expect("failed to run an app");
+    if let Err(e) = run(args) {
+        eprintln!("error: {e}");
+        if let Some(hint) = e.kind.hint() {
+            eprintln!("hint: {hint}");
+        }
+        std::process::exit(e.kind.exit_code());
+    }
+}
+
+fn run(args: Args) -> Result<(), Error> {
+    let args = config::apply(args)?;
+    if args.print_config {
+        print!("{}", config::to_string(&args));
+        return Ok(());
+    }
+    if args.list_adapters {
+        for adapter in raytracer::list_adapters(args.backend) {
+            println!(
+                "{}: {} ({}, {})",
+                adapter.index, adapter.name, adapter.backend, adapter.device_type
+            );
+        }
+        return Ok(());
+    }
+    let world = load_scene(args.scene.as_deref()).map_err(|e| Error::new(ErrorKind::Scene, e))?;
+    if args.print_scene {
+        println!("{}", world.to_string(raytracer::SceneFormat::Ron));
+        return Ok(());
+    }
+    if let Some(animation) = args.animate {
+        let mut args = args;
+        set_headless_size(&mut args);
+        args.max_samples = Some(
+            args.samples_per_animation_frame
+                .or(args.max_samples)
+                .unwrap_or(DEFAULT_OUTPUT_SAMPLES),
+        );
+        return animation::run(args, &world, animation);
+    }
+    if let Some(frames) = args.bench {
+        let mut args = args;
+        set_headless_size(&mut args);
+        let json = args.bench_json.clone();
+        let report = bench::run(args, &world, frames)?;
+        report.print();
+        if let Some(path) = json {
+            report.write_json(&path)?;
+        }
+        return Ok(());
+    }
+    if args.output.is_some() || args.verify_reproducible {
+        return render_headless(args, &world);
+    }
+    let event_loop = EventLoop::with_user_event().build().map_err(|e| {
+        Error::new(
+            ErrorKind::EventLoop,
+            format_args!("can't create an event loop: {e}"),
+        )
+    })?;
+    let mut app = App::new(&event_loop, args.into(), world, raytracer::PlatformArgs {});
+    let handle = app.handle();
+    event_loop
+        .run_app(&mut app)
+        .map_err(|e| Error::new(ErrorKind::EventLoop, e))?;
+    match handle.init_error() {
+        Some(e) => Err(e.into()),
+        None => Ok(()),
+    }
+}
+
+/// Samples accumulated for `--output` unless `--max-samples` is given
+const DEFAULT_OUTPUT_SAMPLES: u32 = 256;
+/// Resolution for `--output` and `--bench` if neither `--width` nor `--height` is given
+const DEFAULT_OUTPUT_SIZE: [u32; 2] = [800, 600];
+/// How often `--stats` prints progress for `--output`
+const HEADLESS_STATS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Accumulates `--max-samples` without a window, saves the image as PNG and
+/// optionally checks that a second render with the same seed matches it
+fn render_headless(mut args: Args, world: &raytracer::World) -> Result<(), Error> {
+    if args.max_samples.is_none() {
+        log::warn!("--max-samples is not set, rendering {DEFAULT_OUTPUT_SAMPLES} samples");
+        args.max_samples = Some(DEFAULT_OUTPUT_SAMPLES);
+    }
+    set_headless_size(&mut args);
+
+    let (png, seed) = render_png(args.clone(), world)?;
+    if args.verify_reproducible {
+        args.seed = Some(seed);
+        let (again, _) = render_png(args.clone(), world)?;
+        if again != png {
+            return Err(Error::new(
+                ErrorKind::NotReproducible,
+                format_args!("renders with seed {seed} differ"),
+            ));
+        }
+        println!("Renders with seed {seed} are identical");
+    }
+    if let Some(output) = &args.output {
+        std::fs::write(output, png).map_err(|e| {
+            Error::new(
+                ErrorKind::Output,
+                format_args!("can't write `{}`: {e}", output.display()),
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Without a window a zero side is taken from the other one or the default size
+fn set_headless_size(args: &mut Args) {
+    [args.width, args.height] = match [args.width, args.height] {
+        [0, 0] => DEFAULT_OUTPUT_SIZE,
+        [side, 0] | [0, side] => [side; 2],
+        size => size,
+    };
+}
+
+/// Returns the encoded image and the seed it was rendered with
+fn render_png(args: Args, world: &raytracer::World) -> Result<(Vec<u8>, u64), Error> {
+    let (exposure, tonemap, stats) = (args.exposure, args.tonemap, args.stats);
+    let mut app = raytracer::HeadlessApp::new(args.into(), world)?;
+    let mut shown_at = Instant::now();
+    while !app.is_converged() {
+        let frame_started = Instant::now();
+        app.redraw();
+        if stats && shown_at.elapsed() >= HEADLESS_STATS_INTERVAL {
+            shown_at = Instant::now();
+            let progress = app.progress();
+            println!(
+                "{}x{} traced | {} spp, {:.1} spp/s, {:.1} ms",
+                progress.width,
+                progress.height,
+                progress.samples,
+                progress.samples_per_second(),
+                frame_started.elapsed().as_secs_f64() * 1000.0,
+            );
+        }
+    }
+    let progress = app.progress();
+    let mut image = app
+        .capture()
+        .ok_or_else(|| Error::new(ErrorKind::DeviceFailed, "failed to read back the image"))?;
+    image.tonemap(exposure, tonemap);
+
+    println!(
+        "Rendered {} samples per pixel at {}x{} in {:.2}s, {:.1} samples/s",
+        progress.samples,
+        progress.width,
+        progress.height,
+        progress.elapsed.as_secs_f64(),
+        progress.samples_per_second(),
+    );
+    Ok((image.to_png(), app.seed()))
+}
+
+/// Parses a finite number greater than zero
+fn positive(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(x) if x.is_finite() && x > 0.0 => Ok(x),
+        Ok(_) => Err("must be a positive number".to_owned()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn resolution_scale(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(x) if (0.5..=2.0).contains(&x) => Ok(x),
+        Ok(_) => Err("must be from 0.5 to 2".to_owned()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Resolves `--scene`, builtin names take precedence over paths
+fn load_scene(scene: Option<&str>) -> Result<raytracer::World, String> {
+    let Some(scene) = scene else {
+        return Ok(raytracer::World::default());
+    };
+    if let Some(world) = raytracer::World::builtin(scene) {
+        return Ok(world);
+    }
+    let builtins = raytracer::World::BUILTINS.join(", ");
+    let source = std::fs::read_to_string(scene).map_err(|e| {
+        format!("can't read scene `{scene}`: {e}\n(available builtin scenes: {builtins})")
+    })?;
+    raytracer::World::parse(&source, raytracer::SceneFormat::from_path(scene))
+        .map_err(|e| format!("can't parse scene `{scene}`: {e}"))
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug, serde::Serialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(clap::Parser))]
+#[clap(after_help = error::EXIT_CODES, args_override_self = true)]
 pub struct Args {
+    /// TOML file with defaults for the other flags, `myraytracer.toml` if present
+    #[clap(long)]
+    #[serde(skip)]
+    config: Option<PathBuf>,
+    /// Print the effective configuration as TOML and exit
+    #[clap(long)]
+    #[serde(skip)]
+    print_config: bool,
     #[clap(long, default_value_t = 0)]
     width: u32,
     #[clap(long, default_value_t = 0)]
     height: u32,
+    /// Borderless fullscreen on the current monitor, Escape switches to a window
+    #[clap(long, conflicts_with_all = ["width", "height"])]
+    fullscreen: bool,
+    /// One of `auto`, `fifo`, `mailbox` or `immediate`, unsupported modes fall back to vsync
+    #[clap(long, default_value_t = raytracer::PresentMode::Auto)]
+    #[serde(serialize_with = "config::serialize_display")]
+    present_mode: raytracer::PresentMode,
+    /// Print available adapters with their indices and exit
+    #[clap(long)]
+    #[serde(skip)]
+    list_adapters: bool,
+    /// Adapter index from `--list-adapters` or a substring of its name,
+    /// overrides `WGPU_ADAPTER_NAME`
+    #[clap(long)]
+    adapter: Option<String>,
+    /// One of `vulkan`, `metal`, `dx12` or `gl`, overrides `WGPU_BACKEND`
+    #[clap(long)]
+    backend: Option<raytracer::GpuBackend>,
     #[clap(long, default_value_t = 1)]
     samples_per_frame: u32,
     #[clap(long, default_value_t = 50)]
     ray_depth: u32,
     #[clap(long, default_value_t = 1.0)]
     max_framebuffer_weight: f32,
+    /// Ratio of the traced resolution to the window size, from 0.5 to 2
+    #[clap(long, default_value_t = 1.0, value_parser = resolution_scale)]
+    resolution_scale: f32,
+    /// Linear multiplier applied to radiance before tone mapping
+    #[clap(long, default_value_t = 1.0, value_parser = positive)]
+    exposure: f32,
+    /// One of `none`, `reinhard` or `aces`
+    #[clap(long, default_value_t = raytracer::Tonemap::None)]
+    #[serde(serialize_with = "config::serialize_display")]
+    tonemap: raytracer::Tonemap,
+    /// Encoding exponent, e.g. 2.2, only honored when the window surface isn't sRGB
+    #[clap(long, value_parser = positive)]
+    gamma: Option<f32>,
+    #[clap(long)]
+    rr_start_depth: Option<u32>,
+    /// Keep rendering at the initial resolution and disallow window resizing
+    #[clap(long)]
+    lock_resolution: bool,
+    /// Builtin scene name or a path to a RON or JSON scene file
+    #[clap(long)]
+    scene: Option<String>,
+    /// Print the scene as RON and exit
+    #[clap(long)]
+    #[serde(skip)]
+    print_scene: bool,
+    /// Render without a window into a PNG file and exit
+    #[clap(long)]
+    #[serde(skip)]
+    output: Option<PathBuf>,
+    /// Stop tracing once this many samples per pixel are accumulated
+    #[clap(long)]
+    max_samples: Option<u32>,
+    /// Seed for per-pixel random numbers, random if not set and printed in the log
+    ///
+    /// Images are reproducible only with the same seed, scene, resolution, samples per frame,
+    /// max samples, ray depth and russian roulette depth, on the same GPU and driver.
+    #[clap(long)]
+    #[serde(serialize_with = "config::serialize_seed")]
+    seed: Option<u64>,
+    /// Render twice without a window and fail unless both images are identical
+    #[clap(long)]
+    #[serde(skip)]
+    verify_reproducible: bool,
+    /// Time this many frames without a window and print a summary
+    #[clap(long, value_parser = clap::value_parser!(u32).range(1..), conflicts_with_all = ["output", "verify_reproducible"])]
+    #[serde(skip)]
+    bench: Option<u32>,
+    /// Also write the `--bench` summary as JSON
+    #[clap(long, requires = "bench")]
+    #[serde(skip)]
+    bench_json: Option<PathBuf>,
+    /// Render a camera animation without a window into `--output-dir`, only `orbit` for now
+    #[clap(long, requires = "output_dir", conflicts_with_all = ["output", "verify_reproducible", "bench"])]
+    #[serde(skip)]
+    animate: Option<animation::Animation>,
+    /// Number of `--animate` frames
+    #[clap(long, default_value_t = 120, value_parser = clap::value_parser!(u32).range(1..))]
+    #[serde(skip)]
+    frames: u32,
+    /// Directory for `frame_0001.png` onwards and the progress of `--animate`
+    #[clap(long, requires = "animate")]
+    #[serde(skip)]
+    output_dir: Option<PathBuf>,
+    /// Samples per pixel of every `--animate` frame, `--max-samples` or 256 if not set
+    #[clap(long, requires = "animate")]
+    #[serde(skip)]
+    samples_per_animation_frame: Option<u32>,
+    /// Continue an interrupted `--animate` with the seed it has recorded
+    #[clap(long, requires = "animate", conflicts_with = "seed")]
+    #[serde(skip)]
+    resume_animation: bool,
+    /// Initial window title
+    #[clap(long)]
+    title: Option<String>,
+    /// Show samples, samples per second and frame time in the window title,
+    /// or print them every few seconds with `--output`
+    #[clap(long)]
+    stats: bool,
+    /// Log debug messages and print a backtrace on internal errors
+    #[clap(long)]
+    #[serde(skip)]
+    verbose: bool,
 }
 
 impl From<Args> for raytracer::Args {
@@ -38,6 +345,21 @@ impl From<Args> for raytracer::Args {
             samples_per_frame: args.samples_per_frame,
             ray_depth: args.ray_depth,
             max_framebuffer_weight: args.max_framebuffer_weight,
+            resolution_scale: args.resolution_scale,
+            exposure: args.exposure,
+            tonemap: args.tonemap,
+            gamma: args.gamma,
+            rr_start_depth: args.rr_start_depth,
+            lock_resolution: args.lock_resolution,
+            max_samples: args.max_samples,
+            seed: args.seed,
+            backend: args.backend,
+            adapter: args.adapter,
+            title: args.title,
+            stats: args.stats,
+            fullscreen: args.fullscreen,
+            present_mode: args.present_mode,
+            ..raytracer::Args::default()
         }
     }
 }
@@ -0,0 +1,74 @@
+use std::{
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+/// Runs the binary away from any `myraytracer.toml` of the source tree
+fn native_runner(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_native-runner"))
+        .args(args)
+        .current_dir(env!("CARGO_TARGET_TMPDIR"))
+        .output()
+        .expect("running native-runner")
+}
+
+fn write_config(name: &str, contents: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::write(&path, contents).expect("writing config");
+    path
+}
+
+fn print_config(args: &[&str]) -> String {
+    let output = native_runner(&[args, &["--print-config"]].concat());
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn flags_override_config() {
+    let config = write_config(
+        "precedence.toml",
+        "width = 320\nheight = 240\nexposure = 2.0\ntonemap = \"aces\"\n",
+    );
+    let printed = print_config(&["--config", config.to_str().unwrap(), "--width", "640"]);
+    let printed: toml::Table = printed.parse().unwrap();
+    assert_eq!(printed["width"].as_integer(), Some(640));
+    assert_eq!(printed["height"].as_integer(), Some(240));
+    assert_eq!(printed["exposure"].as_float(), Some(2.0));
+    assert_eq!(printed["tonemap"].as_str(), Some("aces"));
+}
+
+#[test]
+fn print_config_skips_run_mode() {
+    let printed = print_config(&["--output", "out.png", "--verify-reproducible"]);
+    let printed: toml::Table = printed.parse().unwrap();
+    for key in [
+        "output",
+        "verify_reproducible",
+        "bench",
+        "animate",
+        "frames",
+    ] {
+        assert!(!printed.contains_key(key), "`{key}` in {printed:?}");
+    }
+}
+
+#[test]
+fn malformed_config() {
+    for (name, contents) in [
+        ("unparsable.toml", "width = \n"),
+        ("wrong_type.toml", "width = \"wide\"\n"),
+    ] {
+        let config = write_config(name, contents);
+        let output = native_runner(&["--config", config.to_str().unwrap(), "--print-config"]);
+        assert_eq!(output.status.code(), Some(11), "{output:?}");
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains(name), "{stderr}");
+    }
+}
+
+#[test]
+fn missing_scene() {
+    let output = native_runner(&["--scene", "no-such-scene.ron", "--print-scene"]);
+    assert_eq!(output.status.code(), Some(8), "{output:?}");
+}
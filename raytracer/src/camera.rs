@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use winit::{
+    dpi::PhysicalPosition,
+    event::{ElementState, MouseButton, MouseScrollDelta, Touch, TouchPhase, WindowEvent},
+};
+
+/// Camera orbiting around `target` and looking at it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub target: [f32; 3],
+    pub distance: f32,
+    /// Rotation around the vertical axis in radians, zero looks towards `-z`
+    pub yaw: f32,
+    /// Elevation above the target in radians
+    pub pitch: f32,
+    /// Vertical field of view in degrees
+    pub vfov: f32,
+}
+
+impl Default for Camera {
+    /// Looks from the origin towards `-z`
+    fn default() -> Self {
+        Camera {
+            target: [0.0, 0.0, -1.0],
+            distance: 1.0,
+            yaw: 0.0,
+            pitch: 0.0,
+            vfov: 90.0,
+        }
+    }
+}
+
+impl Camera {
+    /// Unit vector pointing from the target to the camera
+    fn backward(&self) -> [f32; 3] {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        [sin_yaw * cos_pitch, sin_pitch, cos_yaw * cos_pitch]
+    }
+
+    pub fn position(&self) -> [f32; 3] {
+        let backward = self.backward();
+        [0, 1, 2].map(|i| self.target[i] + self.distance * backward[i])
+    }
+
+    /// Position followed by right, up and backward vectors
+    pub(crate) fn frame(&self) -> [[f32; 3]; 4] {
+        let backward = self.backward();
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        // Cross product of the world's up and backward vectors, normalized
+        let right = [cos_yaw, 0.0, -sin_yaw];
+        let up = cross(backward, right);
+        [self.position(), right, up, backward]
+    }
+
+    pub(crate) fn focal_length(&self) -> f32 {
+        1.0 / (self.vfov.to_radians() / 2.0).tan()
+    }
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/// Radians of rotation per pixel of dragging
+const ROTATE_SPEED: f32 = 0.005;
+/// Fraction of the distance dollied per scrolled line
+const ZOOM_SPEED: f32 = 0.1;
+const MAX_PITCH: f32 = 89.0 * std::f32::consts::PI / 180.0;
+const MIN_DISTANCE: f32 = 0.01;
+
+/// Orbits the camera by dragging with the left mouse button or a single finger,
+/// dollies by scrolling or pinching
+#[derive(Default)]
+pub(crate) struct CameraControls {
+    dragging: bool,
+    cursor: Option<PhysicalPosition<f64>>,
+    touches: HashMap<u64, PhysicalPosition<f64>>,
+}
+
+impl CameraControls {
+    /// Returns whether the camera has changed
+    pub(crate) fn handle(&mut self, event: &WindowEvent, camera: &mut Camera) -> bool {
+        match *event {
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => {
+                self.dragging = state == ElementState::Pressed;
+                false
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let last = self.cursor.replace(position);
+                match last {
+                    Some(last) if self.dragging => {
+                        rotate(camera, position.x - last.x, position.y - last.y)
+                    }
+                    _ => false,
+                }
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor = None;
+                self.dragging = false;
+                false
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y,
+                    // Browsers and touchpads report pixels, roughly 40 per line
+                    MouseScrollDelta::PixelDelta(delta) => delta.y as f32 / 40.0,
+                };
+                dolly(camera, (-lines * ZOOM_SPEED).exp())
+            }
+            WindowEvent::Touch(touch) => self.handle_touch(touch, camera),
+            _ => false,
+        }
+    }
+
+    fn handle_touch(&mut self, touch: Touch, camera: &mut Camera) -> bool {
+        match touch.phase {
+            TouchPhase::Started => {
+                self.touches.insert(touch.id, touch.location);
+                false
+            }
+            TouchPhase::Moved => {
+                let pinch_before = self.pinch_distance();
+                let Some(last) = self.touches.insert(touch.id, touch.location) else {
+                    return false;
+                };
+                match (self.touches.len(), pinch_before, self.pinch_distance()) {
+                    (1, ..) => rotate(camera, touch.location.x - last.x, touch.location.y - last.y),
+                    (2, Some(before), Some(after)) if after > 0.0 => {
+                        dolly(camera, (before / after) as f32)
+                    }
+                    _ => false,
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touches.remove(&touch.id);
+                false
+            }
+        }
+    }
+
+    /// Distance between fingers if exactly two are down
+    fn pinch_distance(&self) -> Option<f64> {
+        let mut touches = self.touches.values();
+        match (touches.next(), touches.next(), touches.next()) {
+            (Some(a), Some(b), None) => Some((a.x - b.x).hypot(a.y - b.y)),
+            _ => None,
+        }
+    }
+}
+
+fn rotate(camera: &mut Camera, dx: f64, dy: f64) -> bool {
+    if dx == 0.0 && dy == 0.0 {
+        return false;
+    }
+    camera.yaw -= dx as f32 * ROTATE_SPEED;
+    camera.pitch = (camera.pitch + dy as f32 * ROTATE_SPEED).clamp(-MAX_PITCH, MAX_PITCH);
+    true
+}
+
+fn dolly(camera: &mut Camera, factor: f32) -> bool {
+    let distance = (camera.distance * factor).max(MIN_DISTANCE);
+    if distance == camera.distance || !distance.is_finite() {
+        return false;
+    }
+    camera.distance = distance;
+    true
+}
@@ -0,0 +1,70 @@
+use std::fmt;
+
+/// Reason why the app couldn't start rendering
+#[derive(Clone, Debug)]
+pub struct InitError {
+    pub kind: InitErrorKind,
+    pub message: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InitErrorKind {
+    WindowFailed,
+    SurfaceFailed,
+    NoAdapter,
+    DeviceFailed,
+    /// The app was closed before initialization has finished
+    Closed,
+}
+
+impl InitErrorKind {
+    /// Stable identifier, suitable for matching on outside of Rust
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InitErrorKind::WindowFailed => "window-failed",
+            InitErrorKind::SurfaceFailed => "surface-failed",
+            InitErrorKind::NoAdapter => "no-adapter",
+            InitErrorKind::DeviceFailed => "device-failed",
+            InitErrorKind::Closed => "closed",
+        }
+    }
+}
+
+impl InitError {
+    pub(crate) fn new(kind: InitErrorKind, message: impl fmt::Display) -> Self {
+        InitError {
+            kind,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for InitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind.as_str(), self.message)
+    }
+}
+
+impl std::error::Error for InitError {}
+
+/// Scene file couldn't be parsed
+#[derive(Clone, Debug)]
+pub struct SceneError {
+    pub message: String,
+}
+
+impl SceneError {
+    pub(crate) fn new(message: impl fmt::Display) -> Self {
+        SceneError {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for SceneError {}
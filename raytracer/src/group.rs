@@ -0,0 +1,191 @@
+use crate::{App, AppEvent, AppEventDispatch, Args, PlatformArgs, World};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt, mem,
+    rc::Rc,
+};
+use web_time::Instant;
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy},
+    window::WindowId,
+};
+
+/// Identifies an app within its [`AppGroup`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AppId(u32);
+
+thread_local! {
+    static CURRENT: Cell<Option<AppId>> = const { Cell::new(None) };
+}
+
+impl AppId {
+    /// The app whose event is being handled on this thread, e.g. to attribute log messages
+    pub fn current() -> Option<AppId> {
+        CURRENT.get()
+    }
+}
+
+impl fmt::Display for AppId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "app {}", self.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum AppGroupEvent {
+    /// Apps were queued by [`AppGroupHandle::add`]
+    Added,
+    App(AppId, AppEvent),
+}
+
+/// Runs several apps, each with its own window, on a single event loop
+///
+/// Winit allows only one event loop per process or page, so this is the way to show
+/// multiple canvases. Closing an app keeps the others and the event loop running.
+pub struct AppGroup {
+    apps: HashMap<AppId, App>,
+    windows: HashMap<WindowId, AppId>,
+    handle: AppGroupHandle,
+    resumed: bool,
+}
+
+/// Adds apps to an [`AppGroup`] from outside of its event loop
+#[derive(Clone)]
+pub struct AppGroupHandle {
+    proxy: EventLoopProxy<AppGroupEvent>,
+    shared: Rc<Shared>,
+}
+
+#[derive(Default)]
+struct Shared {
+    next_id: Cell<u32>,
+    pending: RefCell<Vec<App>>,
+}
+
+impl AppGroup {
+    pub fn new(event_loop: &EventLoop<AppGroupEvent>) -> Self {
+        AppGroup {
+            apps: HashMap::new(),
+            windows: HashMap::new(),
+            handle: AppGroupHandle {
+                proxy: event_loop.create_proxy(),
+                shared: <_>::default(),
+            },
+            resumed: false,
+        }
+    }
+
+    pub fn handle(&self) -> AppGroupHandle {
+        self.handle.clone()
+    }
+
+    /// Runs `f` with the app marked as [current](AppId::current), drops it once closed
+    fn dispatch(&mut self, id: AppId, f: impl FnOnce(&mut App)) {
+        let Some(app) = self.apps.get_mut(&id) else {
+            return;
+        };
+        let previous = CURRENT.replace(Some(id));
+        f(app);
+        CURRENT.set(previous);
+
+        if let Some(window_id) = app.window_id {
+            self.windows.insert(window_id, id);
+        }
+        if app.is_closed() {
+            if let Some(window_id) = app.window_id {
+                self.windows.remove(&window_id);
+            }
+            self.apps.remove(&id);
+        }
+    }
+}
+
+impl AppGroupHandle {
+    /// Creates an app to be run by this group once [added](Self::add)
+    pub fn create_app(&self, args: Args, world: World, platform: PlatformArgs) -> App {
+        let id = AppId(self.shared.next_id.get());
+        self.shared.next_id.set(id.0 + 1);
+        let dispatch = AppEventDispatch::new(self.proxy.clone(), move |event| {
+            AppGroupEvent::App(id, event)
+        });
+        App::with_dispatch(dispatch, Some(id), args, world, platform)
+    }
+
+    /// Hands the app over to the group, returns `false` if its event loop is gone
+    ///
+    /// # Panics
+    ///
+    /// If the app wasn't created by [`create_app`](Self::create_app).
+    pub fn add(&self, app: App) -> bool {
+        assert!(app.id.is_some(), "app doesn't belong to a group");
+        self.shared.pending.borrow_mut().push(app);
+        self.proxy.send_event(AppGroupEvent::Added).is_ok()
+    }
+}
+
+impl ApplicationHandler<AppGroupEvent> for AppGroup {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        self.resumed = true;
+        let ids: Vec<_> = self.apps.keys().copied().collect();
+        for id in ids {
+            self.dispatch(id, |app| app.resumed(event_loop));
+        }
+    }
+
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: AppGroupEvent) {
+        match event {
+            AppGroupEvent::Added => {
+                let pending = mem::take(&mut *self.handle.shared.pending.borrow_mut());
+                for app in pending {
+                    let id = app.id.unwrap();
+                    self.apps.insert(id, app);
+                    // Otherwise the window is created along with the others
+                    if self.resumed {
+                        self.dispatch(id, |app| app.resumed(event_loop));
+                    }
+                }
+            }
+            AppGroupEvent::App(id, event) => {
+                self.dispatch(id, |app| app.user_event(event_loop, event))
+            }
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        if let Some(&id) = self.windows.get(&window_id) {
+            self.dispatch(id, |app| app.window_event(event_loop, window_id, event));
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let mut redraw_at = None;
+        let ids: Vec<_> = self.apps.keys().copied().collect();
+        for id in ids {
+            self.dispatch(id, |app| {
+                if let Some(at) = app.poll_redraw() {
+                    redraw_at = Some(redraw_at.map_or(at, |earliest: Instant| earliest.min(at)));
+                }
+            });
+        }
+        event_loop.set_control_flow(match redraw_at {
+            Some(at) => ControlFlow::WaitUntil(at),
+            None => ControlFlow::Wait,
+        });
+    }
+
+    fn suspended(&mut self, event_loop: &ActiveEventLoop) {
+        self.resumed = false;
+        let ids: Vec<_> = self.apps.keys().copied().collect();
+        for id in ids {
+            self.dispatch(id, |app| app.suspended(event_loop));
+        }
+    }
+}
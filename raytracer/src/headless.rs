@@ -0,0 +1,167 @@
+use crate::{Args, BackendInfo, Base, Camera, Image, InitError, Progress, State, World};
+use futures_channel::oneshot;
+use std::{future::Future, pin::pin, task};
+
+/// Renders without a window, e.g. to save images from scripts
+///
+/// `args.width` and `args.height` are the rendering resolution and must not be zero.
+pub struct HeadlessApp {
+    state: State,
+}
+
+impl HeadlessApp {
+    pub fn new(args: Args, world: &World) -> Result<Self, InitError> {
+        let base = block_on(Base::new(None, None, &args))?;
+        Ok(HeadlessApp {
+            state: State::from_base(base, &args, world),
+        })
+    }
+
+    /// Accumulates a frame and waits for the GPU to finish it
+    pub fn redraw(&mut self) {
+        self.state.redraw();
+        self.state.base.device.poll(wgpu::Maintain::Wait);
+    }
+
+    /// Whether `max_samples` have been accumulated, further redraws do nothing
+    pub fn is_converged(&self) -> bool {
+        self.state.is_converged()
+    }
+
+    pub fn backend_info(&self) -> &BackendInfo {
+        &self.state.base.backend_info
+    }
+
+    /// The seed in effect, either [`Args::seed`] or a random one
+    pub fn seed(&self) -> u64 {
+        self.state.subject.seed
+    }
+
+    /// Renders further samples with another seed, resetting accumulation
+    pub fn reseed(&mut self, seed: u64) {
+        self.state.reseed(seed);
+    }
+
+    pub fn camera(&self) -> Camera {
+        self.state.camera
+    }
+
+    /// Moves the camera, resetting accumulation
+    pub fn set_camera(&mut self, camera: Camera) {
+        self.state.set_camera(camera);
+    }
+
+    pub fn progress(&self) -> Progress {
+        self.state.progress()
+    }
+
+    /// Reads back the latest accumulated image
+    pub fn capture(&self) -> Option<Image> {
+        let (sender, receiver) = oneshot::channel();
+        self.state.capture(vec![sender]);
+        block_on(receiver).ok()
+    }
+}
+
+/// Natively wgpu resolves its futures while polling the device, so busy polling suffices
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = task::Context::from_waker(task::Waker::noop());
+    loop {
+        if let task::Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        std::thread::yield_now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(seed: u64) -> Args {
+        Args {
+            width: 48,
+            height: 48,
+            samples_per_frame: 16,
+            ray_depth: 64,
+            max_samples: Some(1024),
+            seed: Some(seed),
+            ..Args::default()
+        }
+    }
+
+    fn render(args: Args) -> Image {
+        let mut app = HeadlessApp::new(args, &World::default()).expect("creating a headless app");
+        while !app.is_converged() {
+            app.redraw();
+        }
+        app.capture().expect("reading back the image")
+    }
+
+    /// Mean absolute difference of color channels
+    fn error(a: &Image, b: &Image) -> f32 {
+        let sum: f32 = a
+            .pixels
+            .iter()
+            .zip(&b.pixels)
+            .flat_map(|(a, b)| (0..3).map(move |c| (a[c] - b[c]).abs()))
+            .sum();
+        sum / (3 * a.pixels.len()) as f32
+    }
+
+    /// Mean of each color channel
+    fn mean(image: &Image) -> [f32; 3] {
+        let sum = image
+            .pixels
+            .iter()
+            .fold([0.0; 3], |sum, pixel| [0, 1, 2].map(|c| sum[c] + pixel[c]));
+        sum.map(|c| c / image.pixels.len() as f32)
+    }
+
+    #[test]
+    fn russian_roulette() {
+        let reference = render(args(1));
+        // Error of the reference itself with another seed
+        let noise = error(&render(args(2)), &reference);
+        let roulette = render(Args {
+            rr_start_depth: Some(1),
+            ..args(3)
+        });
+        let rr_error = error(&roulette, &reference);
+        let [reference_mean, rr_mean] = [&reference, &roulette].map(mean);
+
+        // Roulette adds variance but no bias
+        assert!(
+            rr_error < 2.0 * noise,
+            "error {rr_error} with noise {noise}"
+        );
+        for c in 0..3 {
+            assert!(
+                (rr_mean[c] - reference_mean[c]).abs() < 0.01 * reference_mean[c],
+                "mean {rr_mean:?} of reference {reference_mean:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn same_seed_is_reproducible() {
+        let args = || Args {
+            max_samples: Some(64),
+            ..args(7)
+        };
+        let [first, second] = [args(), args()].map(render);
+        assert!(
+            first.pixels == second.pixels,
+            "renders with the same seed differ"
+        );
+        let other = render(Args {
+            seed: Some(8),
+            ..args()
+        });
+        assert!(
+            other.pixels != first.pixels,
+            "renders with different seeds match"
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use crate::Tonemap;
+use std::mem;
+
+/// Linear RGBA image read back from the accumulation framebuffer
+#[derive(Clone, Debug)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    /// Pixels in row-major order, top row first
+    pub pixels: Vec<[f32; 4]>,
+}
+
+impl Image {
+    /// Unpacks a framebuffer copy, whose rows are padded and stored bottom row first
+    pub(crate) fn from_framebuffer_rows(
+        width: u32,
+        height: u32,
+        bytes_per_row: u32,
+        data: &[u8],
+    ) -> Self {
+        let texel_size = mem::size_of::<[f32; 4]>();
+        let pixels = data
+            .chunks_exact(bytes_per_row as usize)
+            .take(height as usize)
+            .rev()
+            .flat_map(|row| row[..width as usize * texel_size].chunks_exact(texel_size))
+            .map(bytemuck::pod_read_unaligned)
+            .collect();
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Applies exposure and tone mapping like the window does, keeping alpha
+    pub fn tonemap(&mut self, exposure: f32, tonemap: Tonemap) {
+        for pixel in &mut self.pixels {
+            let [r, g, b] = tonemap.apply([pixel[0], pixel[1], pixel[2]].map(|c| exposure * c));
+            pixel[..3].copy_from_slice(&[r, g, b]);
+        }
+    }
+
+    /// Encodes the image as an 8-bit sRGB PNG
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+        let data: Vec<u8> = self
+            .pixels
+            .iter()
+            .flat_map(|&[r, g, b, _]| [r, g, b].map(linear_to_srgb8))
+            .collect();
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&data))
+            .expect("encoding PNG into memory");
+        png
+    }
+}
+
+/// Applies the sRGB transfer function and quantizes with rounding
+pub fn linear_to_srgb8(linear: f32) -> u8 {
+    let linear = linear.clamp(0.0, 1.0);
+    let srgb = if linear <= 0.0031308 {
+        12.92 * linear
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    };
+    (srgb * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srgb_reference_values() {
+        // 0.0031308 ends the linear segment at 0.04045, 0.5 encodes to 0.7354
+        for (linear, srgb) in [(0.0, 0), (0.0031308, 10), (0.5, 188), (1.0, 255)] {
+            assert_eq!(linear_to_srgb8(linear), srgb, "encoding {linear}");
+        }
+    }
+
+    #[test]
+    fn srgb_clamps_out_of_range() {
+        for (linear, srgb) in [(-1.0, 0), (-0.0, 0), (1.5, 255), (f32::INFINITY, 255)] {
+            assert_eq!(linear_to_srgb8(linear), srgb, "encoding {linear}");
+        }
+    }
+}
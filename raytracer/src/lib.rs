@@ -1,27 +1,95 @@
 use bytemuck::{Pod, Zeroable};
+use camera::CameraControls;
+use futures_channel::oneshot;
 use rand::Rng;
-use rand_xoshiro::rand_core::SeedableRng;
-use std::{borrow::Cow, future::Future, mem, num::NonZeroU64, pin::Pin, sync::Arc, task};
+use rand_xoshiro::rand_core::{RngCore, SeedableRng};
+use std::{
+    borrow::Cow,
+    fmt,
+    future::Future,
+    mem,
+    num::NonZeroU64,
+    pin::Pin,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task,
+};
 use waker::AppEventDispatchWaker;
+use web_time::{Duration, Instant};
 use wgpu::util::DeviceExt;
 use winit::{
     dpi,
-    event::WindowEvent,
-    event_loop::{ActiveEventLoop, EventLoop, EventLoopProxy},
-    window::{Window, WindowId},
+    event::{ElementState, KeyEvent, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy},
+    keyboard::{Key, NamedKey},
+    window::{Fullscreen, Window, WindowId},
 };
 
+mod camera;
+mod error;
+mod group;
+#[cfg(not(target_arch = "wasm32"))]
+mod headless;
+mod image;
+#[cfg(target_arch = "wasm32")]
+mod offscreen;
+mod scene;
 mod waker;
 
+pub use camera::Camera;
+pub use error::{InitError, InitErrorKind, SceneError};
+pub use group::{AppGroup, AppGroupEvent, AppGroupHandle, AppId};
+#[cfg(not(target_arch = "wasm32"))]
+pub use headless::HeadlessApp;
+pub use image::{linear_to_srgb8, Image};
+#[cfg(target_arch = "wasm32")]
+pub use offscreen::OffscreenApp;
+pub use scene::{Lambertian, Material, Metal, SceneFormat, Sphere, World};
 pub use winit;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct Args {
     pub width: u32,
     pub height: u32,
     pub samples_per_frame: u32,
     pub ray_depth: u32,
     pub max_framebuffer_weight: f32,
+    /// Bounce after which paths are terminated by russian roulette, disabled if `None`
+    pub rr_start_depth: Option<u32>,
+    /// Seed for per-pixel random number generators, taken from entropy if `None`
+    pub seed: Option<u64>,
+    /// Keep rendering at the initial resolution when the window is resized
+    pub lock_resolution: bool,
+    /// Stop tracing once this many samples per pixel are accumulated
+    pub max_samples: Option<u32>,
+    /// Vertical field of view in degrees
+    pub vfov: f32,
+    /// Linear multiplier applied to radiance before tone mapping
+    pub exposure: f32,
+    pub tonemap: Tonemap,
+    /// Encoding exponent applied after tone mapping, only to surfaces without an sRGB format
+    pub gamma: Option<f32>,
+    pub present_mode: PresentMode,
+    /// Ratio of the rendering resolution to the window size
+    pub resolution_scale: f32,
+    /// Orbit the camera by dragging and dolly it by scrolling or pinching
+    pub camera_controls: bool,
+    /// Backend to use exclusively, otherwise picked by `WGPU_BACKEND` or the platform
+    pub backend: Option<GpuBackend>,
+    /// Index from [`list_adapters`] or a case-insensitive substring of the adapter name,
+    /// otherwise picked by `WGPU_ADAPTER_NAME` or the platform. Ignored on the web.
+    pub adapter: Option<String>,
+    /// Limit on frames per second while accumulating, unlimited if zero
+    pub target_fps: f32,
+    /// Start borderless fullscreen on the current monitor, Escape switches to a window
+    pub fullscreen: bool,
+    /// Initial window title, ignored on the web
+    pub title: Option<String>,
+    /// Periodically append samples, samples per second and frame time to the window title
+    pub stats: bool,
 }
 
 impl Default for Args {
@@ -32,6 +100,313 @@ impl Default for Args {
             ray_depth: 50,
             samples_per_frame: 1,
             max_framebuffer_weight: 1.0,
+            rr_start_depth: None,
+            seed: None,
+            lock_resolution: false,
+            max_samples: None,
+            vfov: 90.0,
+            exposure: 1.0,
+            tonemap: Tonemap::None,
+            gamma: None,
+            present_mode: PresentMode::Auto,
+            resolution_scale: 1.0,
+            camera_controls: true,
+            backend: None,
+            adapter: None,
+            target_fps: 0.0,
+            fullscreen: false,
+            title: None,
+            stats: false,
+        }
+    }
+}
+
+/// Mapping of accumulated radiance into the displayable range
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Tonemap {
+    /// Clamp
+    #[default]
+    None = 0,
+    Reinhard = 1,
+    /// Narkowicz's fit of the ACES filmic curve
+    Aces = 2,
+}
+
+impl Tonemap {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Tonemap::None => "none",
+            Tonemap::Reinhard => "reinhard",
+            Tonemap::Aces => "aces",
+        }
+    }
+
+    /// Same curve as the blit shader applies
+    pub fn apply(self, color: [f32; 3]) -> [f32; 3] {
+        match self {
+            Tonemap::None => color,
+            Tonemap::Reinhard => color.map(|c| c / (1.0 + c)),
+            Tonemap::Aces => color.map(|c| {
+                let a = c * (2.51 * c + 0.03);
+                let b = c * (2.43 * c + 0.59) + 0.14;
+                (a / b).clamp(0.0, 1.0)
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Tonemap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Tonemap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Tonemap::None),
+            "reinhard" => Ok(Tonemap::Reinhard),
+            "aces" => Ok(Tonemap::Aces),
+            _ => Err(format!(
+                "unknown tonemap `{s}`, expected none, reinhard or aces"
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PresentMode {
+    /// Vsync with the lowest latency supported
+    #[default]
+    Auto,
+    Fifo,
+    Mailbox,
+    Immediate,
+}
+
+impl PresentMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PresentMode::Auto => "auto",
+            PresentMode::Fifo => "fifo",
+            PresentMode::Mailbox => "mailbox",
+            PresentMode::Immediate => "immediate",
+        }
+    }
+}
+
+impl fmt::Display for PresentMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PresentMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(PresentMode::Auto),
+            "fifo" => Ok(PresentMode::Fifo),
+            "mailbox" => Ok(PresentMode::Mailbox),
+            "immediate" => Ok(PresentMode::Immediate),
+            _ => Err(format!(
+                "unknown present mode `{s}`, expected auto, fifo, mailbox or immediate"
+            )),
+        }
+    }
+}
+
+/// Graphics API to render with
+///
+/// In browsers WebGPU is preferred when available, otherwise builds with the `webgl`
+/// feature fall back to WebGL2 with downlevel limits and usually lower performance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GpuBackend {
+    Vulkan,
+    Metal,
+    Dx12,
+    /// OpenGL or WebGL2
+    #[serde(alias = "webgl")]
+    Gl,
+    WebGpu,
+}
+
+impl GpuBackend {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GpuBackend::Vulkan => "vulkan",
+            GpuBackend::Metal => "metal",
+            GpuBackend::Dx12 => "dx12",
+            GpuBackend::Gl => "gl",
+            GpuBackend::WebGpu => "webgpu",
+        }
+    }
+
+    /// Backends enabled by `backend`, otherwise by `WGPU_BACKEND` or all of them
+    fn backends(backend: Option<GpuBackend>) -> wgpu::Backends {
+        match backend {
+            Some(backend) => backend.to_wgpu(),
+            None => wgpu::util::backend_bits_from_env().unwrap_or_else(wgpu::Backends::all),
+        }
+    }
+
+    fn to_wgpu(self) -> wgpu::Backends {
+        match self {
+            GpuBackend::Vulkan => wgpu::Backends::VULKAN,
+            GpuBackend::Metal => wgpu::Backends::METAL,
+            GpuBackend::Dx12 => wgpu::Backends::DX12,
+            GpuBackend::Gl => wgpu::Backends::GL,
+            GpuBackend::WebGpu => wgpu::Backends::BROWSER_WEBGPU,
+        }
+    }
+}
+
+impl fmt::Display for GpuBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for GpuBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vulkan" => Ok(GpuBackend::Vulkan),
+            "metal" => Ok(GpuBackend::Metal),
+            "dx12" => Ok(GpuBackend::Dx12),
+            "gl" | "webgl" => Ok(GpuBackend::Gl),
+            "webgpu" => Ok(GpuBackend::WebGpu),
+            _ => Err(format!(
+                "unknown backend `{s}`, expected vulkan, metal, dx12, gl, webgl or webgpu"
+            )),
+        }
+    }
+}
+
+/// Adapter available for [`Args::adapter`]
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Debug)]
+pub struct AdapterInfo {
+    pub index: usize,
+    pub name: String,
+    /// As reported by wgpu, e.g. `Vulkan` or `Gl`
+    pub backend: String,
+    /// As reported by wgpu, e.g. `DiscreteGpu` or `Cpu`
+    pub device_type: String,
+}
+
+/// Adapters of the `backend`, or of the ones from `WGPU_BACKEND`, in selection order
+#[cfg(not(target_arch = "wasm32"))]
+pub fn list_adapters(backend: Option<GpuBackend>) -> Vec<AdapterInfo> {
+    let backends = GpuBackend::backends(backend);
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends,
+        ..<_>::default()
+    });
+    instance
+        .enumerate_adapters(backends)
+        .iter()
+        .enumerate()
+        .map(|(index, adapter)| {
+            let info = adapter.get_info();
+            AdapterInfo {
+                index,
+                name: info.name,
+                backend: format!("{:?}", info.backend),
+                device_type: format!("{:?}", info.device_type),
+            }
+        })
+        .collect()
+}
+
+/// Adapter the app has ended up with
+#[derive(Clone, Debug)]
+pub struct BackendInfo {
+    /// As reported by wgpu, e.g. `BrowserWebGpu` or `Gl`
+    pub backend: String,
+    /// Adapter name, empty if the browser hides it
+    pub name: String,
+    pub driver: String,
+    /// Limits profile requested from the device
+    pub limits: &'static str,
+    pub max_texture_dimension_2d: u32,
+}
+
+/// Rendering parameter that can be changed while the app is running
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "value", rename_all = "snake_case")]
+pub enum Param {
+    SamplesPerFrame(u32),
+    /// Resets accumulation, as samples traced with another depth are biased
+    RayDepth(u32),
+    Exposure(f32),
+    MaxFramebufferWeight(f32),
+    /// See [`Args::target_fps`]
+    TargetFps(f32),
+}
+
+impl Param {
+    /// Returns an error describing the valid range if the value is out of it
+    pub fn validate(self) -> Result<(), String> {
+        let valid = match self {
+            Param::SamplesPerFrame(n) => (1..=MAX_SAMPLES_PER_FRAME).contains(&n),
+            Param::RayDepth(n) => (1..=MAX_RAY_DEPTH).contains(&n),
+            Param::Exposure(x) => x.is_finite() && x > 0.0,
+            Param::MaxFramebufferWeight(w) => (0.0..=1.0).contains(&w),
+            Param::TargetFps(fps) => fps.is_finite() && fps >= 0.0,
+        };
+        if valid {
+            return Ok(());
+        }
+        let range = match self {
+            Param::SamplesPerFrame(_) => format!("between 1 and {MAX_SAMPLES_PER_FRAME}"),
+            Param::RayDepth(_) => format!("between 1 and {MAX_RAY_DEPTH}"),
+            Param::Exposure(_) => "a positive finite number".to_owned(),
+            Param::MaxFramebufferWeight(_) => "between 0 and 1".to_owned(),
+            Param::TargetFps(_) => "a non-negative finite number".to_owned(),
+        };
+        Err(format!(
+            "`{}` must be {range}, got {}",
+            self.name(),
+            self.value()
+        ))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Param::SamplesPerFrame(_) => "samples_per_frame",
+            Param::RayDepth(_) => "ray_depth",
+            Param::Exposure(_) => "exposure",
+            Param::MaxFramebufferWeight(_) => "max_framebuffer_weight",
+            Param::TargetFps(_) => "target_fps",
+        }
+    }
+
+    fn value(self) -> f64 {
+        match self {
+            Param::SamplesPerFrame(n) | Param::RayDepth(n) => n.into(),
+            Param::Exposure(x) | Param::MaxFramebufferWeight(x) | Param::TargetFps(x) => x.into(),
+        }
+    }
+}
+
+/// Upper bounds keeping a single frame from stalling the GPU
+const MAX_SAMPLES_PER_FRAME: u32 = 1024;
+const MAX_RAY_DEPTH: u32 = 1024;
+
+impl From<PresentMode> for wgpu::PresentMode {
+    fn from(mode: PresentMode) -> Self {
+        match mode {
+            PresentMode::Auto => wgpu::PresentMode::AutoVsync,
+            PresentMode::Fifo => wgpu::PresentMode::Fifo,
+            PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+            PresentMode::Immediate => wgpu::PresentMode::Immediate,
         }
     }
 }
@@ -45,9 +420,178 @@ pub struct PlatformArgs {
 #[derive(Copy, Clone, Debug)]
 pub enum AppEvent {
     InitializeWake,
+    /// Exit the event loop, dropping the window and GPU resources
+    Close,
+    /// Stop requesting redraws, keeping accumulated samples
+    Pause,
+    Resume,
+    /// Read back the accumulated image for pending [`AppHandle::capture`] calls
+    Capture,
+    /// Ignored with an error logged if the value is invalid, see [`Param::validate`]
+    SetParam(Param),
+}
+
+/// Sends events to an app, whose event loop may be shared with others
+#[derive(Clone)]
+struct AppEventDispatch(Arc<dyn Fn(AppEvent) -> bool + Send + Sync>);
+
+impl AppEventDispatch {
+    fn new<T: 'static>(
+        proxy: EventLoopProxy<T>,
+        wrap: impl Fn(AppEvent) -> T + Send + Sync + 'static,
+    ) -> Self
+    where
+        EventLoopProxy<T>: Send + Sync,
+    {
+        AppEventDispatch(Arc::new(move |event| proxy.send_event(wrap(event)).is_ok()))
+    }
+
+    /// Returns `false` if the event loop is gone
+    fn send_event(&self, event: AppEvent) -> bool {
+        (self.0)(event)
+    }
+}
+
+/// Accumulation progress, reported after every frame
+#[derive(Clone, Copy, Debug)]
+pub struct Progress {
+    /// Samples accumulated per pixel
+    pub samples: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Time since rendering has started
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// Average since rendering has started
+    pub fn samples_per_second(&self) -> f64 {
+        f64::from(self.samples) / self.elapsed.as_secs_f64()
+    }
+}
+
+/// Controls an [`App`] from outside of its event loop
+#[derive(Clone)]
+pub struct AppHandle {
+    dispatch: AppEventDispatch,
+    status: Arc<AppStatus>,
+}
+
+#[derive(Default)]
+struct AppStatus {
+    closed: AtomicBool,
+    paused: AtomicBool,
+    capture_requests: Mutex<Vec<oneshot::Sender<Image>>>,
+    init: Mutex<InitStatus>,
+    backend_info: Mutex<Option<BackendInfo>>,
+}
+
+enum InitStatus {
+    Pending(Vec<oneshot::Sender<Result<(), InitError>>>),
+    Done(Result<(), InitError>),
 }
 
-type AppEventDispatch = EventLoopProxy<AppEvent>;
+impl Default for InitStatus {
+    fn default() -> Self {
+        InitStatus::Pending(Vec::new())
+    }
+}
+
+impl AppStatus {
+    fn take_capture_requests(&self) -> Vec<oneshot::Sender<Image>> {
+        mem::take(&mut *self.capture_requests.lock().unwrap())
+    }
+
+    /// Notifies [`AppHandle::initialized`] waiters, later results are ignored
+    fn finish_init(&self, result: Result<(), InitError>) {
+        let mut init = self.init.lock().unwrap();
+        if let InitStatus::Pending(waiters) = &mut *init {
+            for waiter in mem::take(waiters) {
+                let _ = waiter.send(result.clone());
+            }
+            *init = InitStatus::Done(result);
+        }
+    }
+}
+
+impl AppHandle {
+    /// Sends an event to the app, returns `false` if its event loop is gone
+    pub fn send_event(&self, event: AppEvent) -> bool {
+        self.dispatch.send_event(event)
+    }
+
+    pub fn close(&self) -> bool {
+        self.send_event(AppEvent::Close)
+    }
+
+    pub fn pause(&self) -> bool {
+        self.send_event(AppEvent::Pause)
+    }
+
+    pub fn resume(&self) -> bool {
+        self.send_event(AppEvent::Resume)
+    }
+
+    /// Changes a rendering parameter, which should pass [`Param::validate`]
+    pub fn set_param(&self, param: Param) -> bool {
+        self.send_event(AppEvent::SetParam(param))
+    }
+
+    /// Returns `false` once the app is closed
+    pub fn is_running(&self) -> bool {
+        !self.status.closed.load(Ordering::Relaxed)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.status.paused.load(Ordering::Relaxed)
+    }
+
+    /// Resolves once the app starts rendering or fails to initialize
+    pub fn initialized(&self) -> impl Future<Output = Result<(), InitError>> {
+        let (sender, receiver) = oneshot::channel();
+        match &mut *self.status.init.lock().unwrap() {
+            InitStatus::Pending(waiters) => waiters.push(sender),
+            InitStatus::Done(result) => {
+                let _ = sender.send(result.clone());
+            }
+        }
+        async move {
+            receiver
+                .await
+                .unwrap_or_else(|_| Err(InitError::new(InitErrorKind::Closed, "app was dropped")))
+        }
+    }
+
+    /// Describes the adapter once initialized
+    pub fn backend_info(&self) -> Option<BackendInfo> {
+        self.status.backend_info.lock().unwrap().clone()
+    }
+
+    /// Returns the initialization error if the app has failed to start
+    pub fn init_error(&self) -> Option<InitError> {
+        match &*self.status.init.lock().unwrap() {
+            InitStatus::Done(Err(e)) if e.kind != InitErrorKind::Closed => Some(e.clone()),
+            _ => None,
+        }
+    }
+
+    /// Reads back the latest accumulated image, resolves to `None` if the app is not running
+    pub fn capture(&self) -> impl Future<Output = Option<Image>> {
+        let (sender, receiver) = oneshot::channel();
+        // Closing takes pending requests after setting `closed`, checking it under the lock
+        // ensures the request is taken then. Apps of a group are gone once closed, so the
+        // event would be ignored while `send_event` still succeeds.
+        let mut requests = self.status.capture_requests.lock().unwrap();
+        if self.is_running() {
+            requests.push(sender);
+            drop(requests);
+            if !self.send_event(AppEvent::Capture) {
+                drop(self.status.take_capture_requests());
+            }
+        }
+        async move { receiver.await.ok() }
+    }
+}
 
 #[derive(Default)]
 enum AppState {
@@ -56,12 +600,13 @@ enum AppState {
     Taken,
     Uninitialized {
         args: Args,
+        world: World,
         platform: PlatformArgs,
         dispatch: AppEventDispatch,
     },
     Initializing {
         waker: task::Waker,
-        future: Pin<Box<dyn Future<Output = State>>>,
+        future: Pin<Box<dyn Future<Output = Result<State, InitError>>>>,
     },
     Running {
         state: State,
@@ -71,17 +616,179 @@ enum AppState {
 
 pub struct App {
     state: AppState,
+    handle: AppHandle,
+    progress_hook: Option<Box<dyn FnMut(Progress, bool)>>,
+    lock_resolution: bool,
+    /// Latest window size and when it was reported
+    pending_resize: Option<(dpi::PhysicalSize<u32>, Instant)>,
+    camera_controls: Option<CameraControls>,
+    /// Set if the app shares its event loop within an [`AppGroup`]
+    id: Option<AppId>,
+    window_id: Option<WindowId>,
+    /// Minimal time between accumulated frames, `None` if unlimited
+    frame_interval: Option<Duration>,
+    /// When the next throttled redraw is due
+    redraw_at: Option<Instant>,
+    stats: Option<TitleStats>,
+}
+
+/// How often [`Args::stats`] refreshes the window title
+const STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Shows [`Progress`] in the window title
+struct TitleStats {
+    title: String,
+    shown_at: Instant,
+    last_frame: Option<Instant>,
+    frame_time: Duration,
 }
 
+impl TitleStats {
+    fn new(title: String) -> Self {
+        TitleStats {
+            title,
+            shown_at: Instant::now(),
+            last_frame: None,
+            frame_time: Duration::ZERO,
+        }
+    }
+
+    /// Records a frame, refreshes the title if it's due or `force` is set
+    fn frame(&mut self, window: &Window, progress: Progress, force: bool) {
+        let now = Instant::now();
+        if let Some(last_frame) = self.last_frame.replace(now) {
+            self.frame_time = now - last_frame;
+        }
+        if !force && now - self.shown_at < STATS_INTERVAL {
+            return;
+        }
+        self.shown_at = now;
+        let window_size = window.inner_size();
+        window.set_title(&format!(
+            "{} | {}x{} window, {}x{} traced | {} spp, {:.1} spp/s, {:.1} ms",
+            self.title,
+            window_size.width,
+            window_size.height,
+            progress.width,
+            progress.height,
+            progress.samples,
+            progress.samples_per_second(),
+            self.frame_time.as_secs_f64() * 1000.0,
+        ));
+    }
+}
+
+/// Time between frames for [`Param::TargetFps`], or `None` if unthrottled
+///
+/// Rates too low for a [`Duration`] saturate to [`Duration::MAX`].
+pub fn frame_interval(target_fps: f32) -> Option<Duration> {
+    if target_fps > 0.0 {
+        Some(Duration::try_from_secs_f32(1.0 / target_fps).unwrap_or(Duration::MAX))
+    } else {
+        None
+    }
+}
+
+/// How long the window size should stay the same before rendering restarts at it
+const RESIZE_DEBOUNCE: Duration = Duration::from_millis(200);
+
 impl App {
-    pub fn new(event_loop: &EventLoop<AppEvent>, args: Args, platform: PlatformArgs) -> Self {
+    pub fn new(
+        event_loop: &EventLoop<AppEvent>,
+        args: Args,
+        world: World,
+        platform: PlatformArgs,
+    ) -> Self {
+        let dispatch = AppEventDispatch::new(event_loop.create_proxy(), |event| event);
+        App::with_dispatch(dispatch, None, args, world, platform)
+    }
+
+    fn with_dispatch(
+        dispatch: AppEventDispatch,
+        id: Option<AppId>,
+        args: Args,
+        world: World,
+        platform: PlatformArgs,
+    ) -> Self {
+        let lock_resolution = args.lock_resolution;
+        let camera_controls = args.camera_controls.then(CameraControls::default);
+        let frame_interval = frame_interval(args.target_fps);
         App {
             state: AppState::Uninitialized {
                 args,
+                world,
                 platform,
-                dispatch: event_loop.create_proxy(),
+                dispatch: dispatch.clone(),
+            },
+            handle: AppHandle {
+                dispatch,
+                status: <_>::default(),
             },
+            progress_hook: None,
+            lock_resolution,
+            pending_resize: None,
+            camera_controls,
+            id,
+            window_id: None,
+            frame_interval,
+            redraw_at: None,
+            stats: None,
+        }
+    }
+
+    /// Identifies the app in its [`AppGroup`], `None` if it runs on its own event loop
+    pub fn id(&self) -> Option<AppId> {
+        self.id
+    }
+
+    pub fn handle(&self) -> AppHandle {
+        self.handle.clone()
+    }
+
+    /// Sets a callback invoked after every rendered frame, along with whether it has converged
+    pub fn set_progress_hook(&mut self, hook: impl FnMut(Progress, bool) + 'static) {
+        self.progress_hook = Some(Box::new(hook));
+    }
+
+    fn is_paused(&self) -> bool {
+        self.handle.is_paused()
+    }
+
+    fn close(&mut self, event_loop: &ActiveEventLoop) {
+        self.state = AppState::Closed;
+        self.handle.status.closed.store(true, Ordering::Relaxed);
+        self.handle.status.finish_init(Err(InitError::new(
+            InitErrorKind::Closed,
+            "app was closed before initialization has finished",
+        )));
+        drop(self.handle.status.take_capture_requests());
+        // Other apps of the group keep running
+        if self.id.is_none() {
+            event_loop.exit();
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        matches!(self.state, AppState::Closed)
+    }
+
+    /// Requests a throttled redraw once it's due, otherwise returns when it will be
+    fn poll_redraw(&mut self) -> Option<Instant> {
+        let at = self.redraw_at?;
+        if Instant::now() < at {
+            return Some(at);
         }
+        self.redraw_at = None;
+        if let AppState::Running { state } = &self.state {
+            state.request_redraw();
+        }
+        None
+    }
+
+    fn fail_init(&mut self, event_loop: &ActiveEventLoop, error: InitError) {
+        log::error!("Failed to initialize: {error}");
+        self.handle.status.finish_init(Err(error));
+        self.close(event_loop);
     }
 
     fn state_as_str(&self) -> &'static str {
@@ -101,6 +808,7 @@ impl winit::application::ApplicationHandler<AppEvent> for App {
         let AppState::Uninitialized {
             platform,
             mut args,
+            world,
             dispatch,
         } = mem::take(&mut self.state)
         else {
@@ -108,7 +816,12 @@ impl winit::application::ApplicationHandler<AppEvent> for App {
         };
 
         #[allow(unused_mut)]
-        let mut attrs = Window::default_attributes().with_resizable(false);
+        let mut attrs = Window::default_attributes()
+            .with_resizable(!args.lock_resolution)
+            .with_fullscreen(args.fullscreen.then_some(Fullscreen::Borderless(None)));
+        if let Some(title) = &args.title {
+            attrs = attrs.with_title(title);
+        }
 
         'set_size: {
             [args.width, args.height] = match args {
@@ -136,24 +849,35 @@ impl winit::application::ApplicationHandler<AppEvent> for App {
         #[cfg(target_arch = "wasm32")]
         {
             use winit::platform::web::WindowAttributesExtWebSys;
+            if args.camera_controls {
+                // Keep touches on the canvas from scrolling the page
+                let _ = platform.canvas.style().set_property("touch-action", "none");
+            }
+            // Default actions are only prevented for the canvas' events, the page scrolls as usual
             attrs = attrs
                 .with_canvas(Some(platform.canvas))
-                .with_prevent_default(false)
+                .with_prevent_default(args.camera_controls)
                 .with_focusable(false);
         }
 
-        let window = event_loop
-            .create_window(attrs)
-            .expect("failed to create a window");
+        let window = match event_loop.create_window(attrs) {
+            Ok(window) => window,
+            Err(e) => {
+                return self.fail_init(event_loop, InitError::new(InitErrorKind::WindowFailed, e))
+            }
+        };
 
-        if args.width == 0 && args.height == 0 {
-            dpi::PhysicalSize {
-                width: args.width,
-                height: args.height,
-            } = window.inner_size();
+        self.window_id = Some(window.id());
+        self.stats = args.stats.then(|| TitleStats::new(window.title()));
+
+        // Canvas sizes are CSS pixels, render at the physical size to stay sharp on HiDPI
+        // displays. Native windows only take the size from the window if none was requested.
+        if cfg!(target_arch = "wasm32") || args.width == 0 && args.height == 0 {
+            let size = window.inner_size();
+            [args.width, args.height] = [size.width, size.height].map(|side| side.max(1));
         }
 
-        let future = Box::pin(async move { State::new(window, &args).await });
+        let future = Box::pin(async move { State::new(window, &args, &world).await });
 
         let waker = AppEventDispatchWaker::new(dispatch, AppEvent::InitializeWake).into_waker();
         // Start initialization
@@ -162,15 +886,64 @@ impl winit::application::ApplicationHandler<AppEvent> for App {
         self.state = AppState::Initializing { waker, future }
     }
 
-    fn user_event(&mut self, _: &ActiveEventLoop, event: AppEvent) {
+    fn user_event(&mut self, event_loop: &ActiveEventLoop, event: AppEvent) {
         log::debug!("User event: {event:?}");
         match event {
             AppEvent::InitializeWake => {
                 if let AppState::Initializing { waker, future } = &mut self.state {
                     let mut cx = task::Context::from_waker(waker);
-                    if let task::Poll::Ready(state) = future.as_mut().poll(&mut cx) {
+                    match future.as_mut().poll(&mut cx) {
+                        task::Poll::Pending => (),
+                        task::Poll::Ready(Ok(state)) => {
+                            if !self.is_paused() {
+                                state.request_redraw();
+                            }
+                            *self.handle.status.backend_info.lock().unwrap() =
+                                Some(state.base.backend_info.clone());
+                            self.state = AppState::Running { state };
+                            self.handle.status.finish_init(Ok(()));
+                        }
+                        task::Poll::Ready(Err(e)) => self.fail_init(event_loop, e),
+                    }
+                }
+            }
+            AppEvent::Close => {
+                log::info!("Close event received, exiting...");
+                self.close(event_loop);
+            }
+            AppEvent::Pause => self.handle.status.paused.store(true, Ordering::Relaxed),
+            AppEvent::Resume => {
+                let was_paused = self.handle.status.paused.swap(false, Ordering::Relaxed);
+                if let (true, AppState::Running { state }) = (was_paused, &self.state) {
+                    state.request_redraw();
+                }
+            }
+            AppEvent::Capture => {
+                let requests = self.handle.status.take_capture_requests();
+                match &self.state {
+                    AppState::Running { state } if !requests.is_empty() => state.capture(requests),
+                    // Dropping senders resolves pending captures with `None`
+                    _ => (),
+                }
+            }
+            AppEvent::SetParam(Param::TargetFps(fps)) => match Param::TargetFps(fps).validate() {
+                Ok(()) => {
+                    self.frame_interval = frame_interval(fps);
+                    // Don't wait out a redraw scheduled at the previous rate
+                    self.redraw_at = None;
+                    if let (false, AppState::Running { state }) = (self.is_paused(), &self.state) {
+                        state.request_redraw();
+                    }
+                }
+                Err(e) => log::error!("Ignoring parameter: {e}"),
+            },
+            AppEvent::SetParam(param) => {
+                let paused = self.is_paused();
+                if let AppState::Running { state } = &mut self.state {
+                    state.set_param(param);
+                    // Present the new value even if converged
+                    if !paused {
                         state.request_redraw();
-                        self.state = AppState::Running { state };
                     }
                 }
             }
@@ -178,17 +951,75 @@ impl winit::application::ApplicationHandler<AppEvent> for App {
     }
 
     fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        if let (Some(controls), AppState::Running { state }) =
+            (&mut self.camera_controls, &mut self.state)
+        {
+            let mut camera = state.camera;
+            if controls.handle(&event, &mut camera) {
+                state.set_camera(camera);
+                state.request_redraw();
+            }
+        }
         match event {
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        logical_key: Key::Named(NamedKey::Escape),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                if let AppState::Running { state } = &self.state {
+                    if let Some(window) = &state.base.window {
+                        window.set_fullscreen(None);
+                    }
+                }
+            }
             WindowEvent::CloseRequested => {
-                self.state = AppState::Closed;
                 log::info!("Close requested, exiting...");
-                event_loop.exit();
+                self.close(event_loop);
+            }
+            WindowEvent::Resized(size) if !self.lock_resolution => {
+                if size.width == 0 || size.height == 0 {
+                    return;
+                }
+                if let AppState::Running { state } = &mut self.state {
+                    state.resize_surface(size);
+                }
+                self.pending_resize = Some((size, Instant::now()));
+                // Rendering may have stopped after converging
+                if let AppState::Running { state } = &self.state {
+                    state.request_redraw();
+                }
             }
+            WindowEvent::RedrawRequested if self.is_paused() => (),
             WindowEvent::RedrawRequested => match &mut self.state {
                 AppState::Initializing { .. } | AppState::Closed => (),
                 AppState::Running { state } => {
+                    let frame_started = Instant::now();
+                    if let Some((size, at)) = self.pending_resize {
+                        if at.elapsed() >= RESIZE_DEBOUNCE {
+                            self.pending_resize = None;
+                            state.resize_surface(size);
+                            state.resize(size);
+                        }
+                    }
                     state.redraw();
-                    state.request_redraw();
+                    if !state.is_converged() || self.pending_resize.is_some() {
+                        // Throttled redraws are requested by `about_to_wait` once due
+                        match self.frame_interval {
+                            // Past the end of time, redraws wait for another rate
+                            Some(interval) => self.redraw_at = frame_started.checked_add(interval),
+                            None => state.request_redraw(),
+                        }
+                    }
+                    if let Some(hook) = &mut self.progress_hook {
+                        hook(state.progress(), state.is_converged());
+                    }
+                    if let (Some(stats), Some(window)) = (&mut self.stats, &state.base.window) {
+                        stats.frame(window, state.progress(), state.is_converged());
+                    }
                 }
                 AppState::Taken | AppState::Uninitialized { .. } => {
                     panic!("Requested redraw but app is {}", self.state_as_str())
@@ -198,6 +1029,13 @@ impl winit::application::ApplicationHandler<AppEvent> for App {
         }
     }
 
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        event_loop.set_control_flow(match self.poll_redraw() {
+            Some(at) => ControlFlow::WaitUntil(at),
+            None => ControlFlow::Wait,
+        });
+    }
+
     fn suspended(&mut self, _: &ActiveEventLoop) {
         // TODO
     }
@@ -210,15 +1048,48 @@ struct State {
     framebuffers: DoubleFramebuffers,
     raytrace_glue: RaytraceGlue,
     framebuffer_glue: FramebufferGlue,
+    /// Frames accumulated since the last reset
     sample_count: u32,
+    /// Samples per pixel accumulated since the last reset
+    samples: u32,
+    started: Instant,
+    max_samples: Option<u32>,
+    resolution_scale: f32,
+    camera: Camera,
 }
 
 impl State {
-    async fn new(window: Window, args: &Args) -> Self {
-        let base = Base::new(window, args).await;
-        let subject = Subject::new(&base, args);
-        let object = Object::new(&base, args);
-        let framebuffers = DoubleFramebuffers::new(&base, args);
+    async fn new(window: Window, args: &Args, world: &World) -> Result<Self, InitError> {
+        let window = Arc::new(window);
+        let target = wgpu::SurfaceTarget::from(Arc::clone(&window));
+        let base = Base::new(Some(target), Some(window), args).await?;
+        Ok(State::from_base(base, args, world))
+    }
+
+    fn from_base(base: Base, args: &Args, world: &World) -> Self {
+        let size = dpi::PhysicalSize::new(args.width, args.height);
+        let render_size = render_size(&base, size, args.resolution_scale);
+        if render_size != scale_size(size, args.resolution_scale) {
+            log::warn!(
+                "Rendering at {}x{}, the largest resolution supported by the GPU",
+                render_size.width,
+                render_size.height
+            );
+        }
+        let render_args = &Args {
+            width: render_size.width,
+            height: render_size.height,
+            ..args.clone()
+        };
+        let camera = Camera {
+            vfov: args.vfov,
+            ..Camera::default()
+        };
+        let mut subject = Subject::new(&base, render_args);
+        subject.set_camera(&camera);
+        subject.update_locals_buffer(&base);
+        let object = Object::new(&base, world);
+        let framebuffers = DoubleFramebuffers::new(&base, render_args);
         let raytrace_glue = RaytraceGlue::new(&base, &subject, &object, &framebuffers);
         let framebuffer_glue = FramebufferGlue::new(&base, &subject, &framebuffers);
 
@@ -230,12 +1101,169 @@ impl State {
             raytrace_glue,
             framebuffer_glue,
             sample_count: 0,
+            samples: 0,
+            started: Instant::now(),
+            max_samples: args.max_samples,
+            resolution_scale: args.resolution_scale,
+            camera,
+        }
+    }
+
+    fn progress(&self) -> Progress {
+        Progress {
+            samples: self.samples,
+            width: self.subject.locals.shape[0],
+            height: self.subject.locals.shape[1],
+            elapsed: self.started.elapsed(),
         }
     }
 
     #[inline]
     fn request_redraw(&self) {
-        self.base.window.request_redraw()
+        if let Some(window) = &self.base.window {
+            window.request_redraw()
+        }
+    }
+
+    /// Copies the latest accumulated image into a buffer and sends it out once mapped
+    fn capture(&self, requests: Vec<oneshot::Sender<Image>>) {
+        let [width, height] = self.subject.locals.shape;
+        let texel_size = self.framebuffers.format.block_copy_size(None).unwrap();
+        let bytes_per_row =
+            (width * texel_size).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        // Natively the map callback has to be `Send`, wasm types aren't `Send` anyway
+        #[allow(clippy::arc_with_non_send_sync)]
+        let buffer = Arc::new(self.base.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("capture"),
+            size: u64::from(bytes_per_row) * u64::from(height),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        }));
+
+        let mut encoder = self
+            .base
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_texture_to_buffer(
+            // Swapped after redraw, so the latest image is the secondary one
+            self.framebuffers.secondary.fb.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.base.queue.submit(Some(encoder.finish()));
+
+        let mapped = Arc::clone(&buffer);
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                if let Err(e) = result {
+                    log::error!("Failed to map capture buffer: {e}");
+                    return;
+                }
+                let image = Image::from_framebuffer_rows(
+                    width,
+                    height,
+                    bytes_per_row,
+                    &mapped.slice(..).get_mapped_range(),
+                );
+                mapped.unmap();
+                for request in requests {
+                    let _ = request.send(image.clone());
+                }
+            });
+
+        // The browser maps buffers on its own, natively wait so it works while paused
+        #[cfg(not(target_arch = "wasm32"))]
+        self.base.device.poll(wgpu::Maintain::Wait);
+    }
+
+    /// Moves the camera, resetting accumulation
+    fn set_camera(&mut self, camera: Camera) {
+        self.camera = camera;
+        self.subject.set_camera(&camera);
+        self.reset_accumulation();
+    }
+
+    /// Applies a parameter, resetting accumulation if the samples traced before are biased
+    fn set_param(&mut self, param: Param) {
+        if let Err(e) = param.validate() {
+            log::error!("Ignoring parameter: {e}");
+            return;
+        }
+        log::info!("Setting {param:?}");
+        let locals = &mut self.subject.locals;
+        match param {
+            Param::SamplesPerFrame(n) => locals.samples_per_frame = n,
+            Param::RayDepth(n) => {
+                locals.ray_depth = n;
+                self.reset_accumulation();
+                return;
+            }
+            Param::Exposure(x) => locals.exposure = x,
+            Param::MaxFramebufferWeight(w) => self.framebuffers.max_framebuffer_weight = w,
+            // Redraws are scheduled by whoever drives them
+            Param::TargetFps(_) => return,
+        }
+        self.subject.update_locals_buffer(&self.base);
+    }
+
+    /// Switches to other random numbers, resetting accumulation
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reseed(&mut self, seed: u64) {
+        self.subject.reseed(&self.base, seed);
+        self.reset_accumulation();
+    }
+
+    /// Discards accumulated samples
+    fn reset_accumulation(&mut self) {
+        self.sample_count = 0;
+        self.samples = 0;
+        self.started = Instant::now();
+        self.subject.locals.framebuffer_weight = 0.0;
+        self.subject.update_locals_buffer(&self.base);
+    }
+
+    /// Reconfigures the surface, the old framebuffers get stretched onto it
+    fn resize_surface(&mut self, size: dpi::PhysicalSize<u32>) {
+        let config = &mut self.base.surface_config;
+        if [config.width, config.height] == [size.width, size.height] {
+            return;
+        }
+        [config.width, config.height] = [size.width, size.height];
+        if let Some(surface) = &self.base.surface {
+            surface.configure(&self.base.device, config);
+        }
+    }
+
+    /// Changes the rendering resolution for the window size, resetting accumulation
+    fn resize(&mut self, size: dpi::PhysicalSize<u32>) {
+        let size = render_size(&self.base, size, self.resolution_scale);
+        let [width, height] = [size.width, size.height];
+        if self.subject.locals.shape == [width, height] {
+            return;
+        }
+        log::info!("Resizing to {width}x{height}");
+        self.subject.resize(&self.base, width, height);
+        self.framebuffers.resize(&self.base, width, height);
+        self.reset_accumulation();
+    }
+
+    /// Whether `max_samples` have been accumulated and tracing should stop
+    fn is_converged(&self) -> bool {
+        self.max_samples
+            .is_some_and(|max_samples| self.progress().samples >= max_samples)
     }
 
     fn redraw(&mut self) {
@@ -244,7 +1272,8 @@ impl State {
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
 
-        {
+        let converged = self.is_converged();
+        if !converged {
             let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: None,
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
@@ -266,12 +1295,23 @@ impl State {
             rpass.set_vertex_buffer(0, self.raytrace_glue.vertices.slice(..));
             rpass.draw(0..4, 0..1);
         }
+        // Once converged only present, the latest image is swapped into the secondary
+        let latest = if converged {
+            &self.framebuffers.secondary
+        } else {
+            &self.framebuffers.target
+        };
 
-        let frame = self.base.surface.get_current_texture().unwrap();
-        let view = frame
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-        {
+        // Headless there's nothing to present to, images are read back by `capture`
+        let frame = self
+            .base
+            .surface
+            .as_ref()
+            .map(|surface| surface.get_current_texture().unwrap());
+        if let Some(frame) = &frame {
+            let view = frame
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default());
             let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: None,
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
@@ -288,51 +1328,120 @@ impl State {
             });
             rpass.set_pipeline(&self.framebuffer_glue.render_pipeline);
             rpass.set_bind_group(0, &self.subject.bind_group, &[]);
-            rpass.set_bind_group(1, &self.framebuffers.target.bind_group, &[]);
+            rpass.set_bind_group(1, &latest.bind_group, &[]);
             rpass.set_vertex_buffer(0, self.framebuffer_glue.vertices.slice(..));
             rpass.draw(0..4, 0..1);
         }
 
         self.base.queue.submit(Some(encoder.finish()));
-        frame.present();
+        if let Some(frame) = frame {
+            frame.present();
+        }
 
+        if converged {
+            return;
+        }
         self.framebuffers.swap();
         self.sample_count = self.sample_count.saturating_add(1);
+        self.samples = self
+            .samples
+            .saturating_add(self.subject.locals.samples_per_frame);
         self.subject.locals.framebuffer_weight = self
             .framebuffers
             .max_framebuffer_weight
             .min(self.sample_count as f32 / (self.sample_count + 1) as f32);
-        self.subject.locals.rng_shuffle = rand::thread_rng().gen();
+        self.subject.locals.rng_shuffle = self.subject.shuffle_rng.gen();
         self.subject.update_locals_buffer(&self.base);
     }
 }
 
+/// Scales each side, keeping at least a pixel
+fn scale_size(size: dpi::PhysicalSize<u32>, scale: f32) -> dpi::PhysicalSize<u32> {
+    let scale = |side: u32| ((side as f32 * scale).round() as u32).max(1);
+    dpi::PhysicalSize::new(scale(size.width), scale(size.height))
+}
+
+/// Scales the size by `resolution_scale`, then shrinks both sides by the same factor
+/// until they fit into textures
+fn render_size(base: &Base, size: dpi::PhysicalSize<u32>, scale: f32) -> dpi::PhysicalSize<u32> {
+    let size = scale_size(size, scale);
+    let max_side = base.device.limits().max_texture_dimension_2d;
+    let longest = size.width.max(size.height);
+    if longest <= max_side {
+        return size;
+    }
+    let size = scale_size(size, max_side as f32 / longest as f32);
+    dpi::PhysicalSize::new(size.width.min(max_side), size.height.min(max_side))
+}
+
 struct Base {
-    window: Arc<Window>,
+    /// `None` if redraws are driven from outside, e.g. by [`OffscreenApp`]
+    window: Option<Arc<Window>>,
+    backend_info: BackendInfo,
     _instance: wgpu::Instance,
-    surface: wgpu::Surface<'static>,
+    /// `None` if headless
+    surface: Option<wgpu::Surface<'static>>,
     _adapter: wgpu::Adapter,
     device: wgpu::Device,
     queue: wgpu::Queue,
+    /// Describes the blit target when headless as well
     surface_config: wgpu::SurfaceConfiguration,
 }
 
 impl Base {
-    async fn new(window: Window, args: &Args) -> Self {
-        let backends = wgpu::util::backend_bits_from_env().unwrap_or_else(wgpu::Backends::all);
+    async fn new(
+        target: Option<wgpu::SurfaceTarget<'static>>,
+        window: Option<Arc<Window>>,
+        args: &Args,
+    ) -> Result<Self, InitError> {
+        let backends = GpuBackend::backends(args.backend);
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends,
             ..<_>::default()
         });
 
-        let window = Arc::new(window);
-        let surface = instance
-            .create_surface(Arc::clone(&window))
-            .expect("failed to create a surface");
-
-        let adapter = wgpu::util::initialize_adapter_from_env_or_default(&instance, Some(&surface))
-            .await
-            .expect("No suitable GPU adapters found on the system!");
+        let surface = target
+            .map(|target| instance.create_surface(target))
+            .transpose()
+            .map_err(|e| InitError::new(InitErrorKind::SurfaceFailed, e))?;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let selected = match &args.adapter {
+            Some(selector) => Some(select_adapter(
+                &instance,
+                backends,
+                selector,
+                surface.as_ref(),
+            )?),
+            None => None,
+        };
+        #[cfg(target_arch = "wasm32")]
+        let selected = None;
+        let adapter = match selected {
+            Some(adapter) => adapter,
+            None => wgpu::util::initialize_adapter_from_env_or_default(&instance, surface.as_ref())
+                .await
+                .ok_or_else(|| match args.backend {
+                    Some(backend) => InitError::new(
+                        InitErrorKind::NoAdapter,
+                        format_args!("forced backend `{backend}` is unavailable"),
+                    ),
+                    None => InitError::new(
+                        InitErrorKind::NoAdapter,
+                        "no suitable GPU adapters found on the system",
+                    ),
+                })?,
+        };
+        let adapter_info = adapter.get_info();
+        // Builds with a single web backend use it regardless of the requested backends
+        if let Some(backend) = args.backend {
+            if !backend.to_wgpu().contains(adapter_info.backend.into()) {
+                return Err(InitError::new(
+                    InitErrorKind::NoAdapter,
+                    format_args!("forced backend `{backend}` is unavailable in this build"),
+                ));
+            }
+        }
 
         let (device, queue) = adapter
             .request_device(
@@ -345,24 +1454,129 @@ impl Base {
                 None,
             )
             .await
-            .expect("Requesting device");
+            .map_err(|e| InitError::new(InitErrorKind::DeviceFailed, e))?;
+
+        let backend_info = BackendInfo {
+            backend: format!("{:?}", adapter_info.backend),
+            name: adapter_info.name,
+            driver: adapter_info.driver,
+            limits: "downlevel-webgl2",
+            max_texture_dimension_2d: device.limits().max_texture_dimension_2d,
+        };
+        log::info!("Using adapter {backend_info:?}");
+        if adapter_info.backend == wgpu::Backend::Gl {
+            log::warn!("Running on the OpenGL/WebGL2 fallback, expect lower performance");
+        }
+
+        let Some(surface) = surface else {
+            let surface_config = wgpu::SurfaceConfiguration {
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                width: args.width,
+                height: args.height,
+                present_mode: wgpu::PresentMode::Fifo,
+                desired_maximum_frame_latency: 2,
+                alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+                view_formats: Vec::new(),
+            };
+            return Ok(Base {
+                window,
+                backend_info,
+                _instance: instance,
+                surface: None,
+                _adapter: adapter,
+                device,
+                queue,
+                surface_config,
+            });
+        };
 
-        let surface_config = surface
+        let mut surface_config = surface
             .get_default_config(&adapter, args.width, args.height)
-            .expect("failed to get default surface config");
+            .ok_or_else(|| {
+                InitError::new(
+                    InitErrorKind::SurfaceFailed,
+                    "surface is not supported by the adapter",
+                )
+            })?;
+        let present_mode = args.present_mode.into();
+        if surface
+            .get_capabilities(&adapter)
+            .present_modes
+            .contains(&present_mode)
+        {
+            surface_config.present_mode = present_mode;
+        } else if args.present_mode != PresentMode::Auto {
+            log::warn!("Present mode {present_mode:?} is unsupported, falling back to vsync");
+        }
 
         surface.configure(&device, &surface_config);
 
-        Base {
+        Ok(Base {
             window,
+            backend_info,
             _instance: instance,
-            surface,
+            surface: Some(surface),
             _adapter: adapter,
             device,
             queue,
             surface_config,
+        })
+    }
+}
+
+/// Picks the adapter for [`Args::adapter`], which must be able to present to the `surface`
+#[cfg(not(target_arch = "wasm32"))]
+fn select_adapter(
+    instance: &wgpu::Instance,
+    backends: wgpu::Backends,
+    selector: &str,
+    surface: Option<&wgpu::Surface>,
+) -> Result<wgpu::Adapter, InitError> {
+    let mut adapters = instance.enumerate_adapters(backends);
+    let index = match selector.parse::<usize>() {
+        Ok(index) if index < adapters.len() => index,
+        Ok(index) => {
+            return Err(InitError::new(
+                InitErrorKind::NoAdapter,
+                format_args!("no adapter #{index}, found {}", adapters.len()),
+            ))
+        }
+        Err(_) => {
+            let needle = selector.to_lowercase();
+            adapters
+                .iter()
+                .position(|adapter| adapter.get_info().name.to_lowercase().contains(&needle))
+                .ok_or_else(|| {
+                    InitError::new(
+                        InitErrorKind::NoAdapter,
+                        format_args!("no adapter name contains `{selector}`"),
+                    )
+                })?
+        }
+    };
+    if let Some(surface) = surface {
+        if !adapters[index].is_surface_supported(surface) {
+            let compatible: Vec<_> = adapters
+                .iter()
+                .enumerate()
+                .filter(|(_, adapter)| adapter.is_surface_supported(surface))
+                .map(|(i, adapter)| format!("#{i} {}", adapter.get_info().name))
+                .collect();
+            let compatible = match compatible.is_empty() {
+                true => "none".to_owned(),
+                false => compatible.join(", "),
+            };
+            return Err(InitError::new(
+                InitErrorKind::NoAdapter,
+                format_args!(
+                    "adapter #{index} {} can't present to the window, compatible adapters: {compatible}",
+                    adapters[index].get_info().name,
+                ),
+            ));
         }
     }
+    Ok(adapters.swap_remove(index))
 }
 
 #[repr(C, align(16))]
@@ -373,12 +1587,22 @@ struct Locals {
     ray_depth: u32,
     rng_shuffle: [u32; 4],
     framebuffer_weight: f32,
-    _padding: [u32; 3],
+    rr_start_depth: u32,
+    focal_length: f32,
+    exposure: f32,
+    tonemap: u32,
+    /// One if the surface encodes sRGB by itself
+    gamma: f32,
+    _padding: [u32; 2],
+    /// Camera position, right, up and backward vectors, `w` is unused
+    camera: [[f32; 4]; 4],
 }
 
 struct Subject {
     locals: Locals,
     locals_buffer: wgpu::Buffer,
+    seed: u64,
+    shuffle_rng: rand_xoshiro::Xoshiro128PlusPlus,
     _rng: wgpu::Texture,
     _rng_view: wgpu::TextureView,
     bind_group_layout: wgpu::BindGroupLayout,
@@ -387,34 +1611,14 @@ struct Subject {
 
 impl Subject {
     fn new(base: &Base, args: &Args) -> Self {
-        let mut seed_rng = rand_xoshiro::SplitMix64::from_entropy();
-
-        let rng_texture_data: Vec<[u32; 4]> = std::iter::repeat_with(|| seed_rng.gen())
-            .filter(|s| s != &[0; 4])
-            .take(args.width as usize * args.height as usize)
-            .collect();
-
-        let rng = base.device.create_texture_with_data(
-            &base.queue,
-            &wgpu::TextureDescriptor {
-                label: None,
-                size: wgpu::Extent3d {
-                    width: args.width,
-                    height: args.height,
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Rgba32Uint,
-                usage: wgpu::TextureUsages::TEXTURE_BINDING,
-                view_formats: &[wgpu::TextureFormat::Rgba32Uint],
-            },
-            <_>::default(),
-            bytemuck::cast_slice(&rng_texture_data),
-        );
+        let seed = args.seed.unwrap_or_else(|| {
+            let seed = rand::random();
+            log::info!("Using random seed {seed}");
+            seed
+        });
+        let shuffle_rng = rand_xoshiro::Xoshiro128PlusPlus::seed_from_u64(seed);
 
-        drop(rng_texture_data);
+        let (rng, rng_view) = Self::create_rng(base, seed, args.width, args.height);
 
         let locals = Locals {
             shape: [args.width, args.height],
@@ -422,7 +1626,20 @@ impl Subject {
             rng_shuffle: [0; 4],
             ray_depth: args.ray_depth,
             framebuffer_weight: 0.0,
-            _padding: [0; 3],
+            rr_start_depth: args.rr_start_depth.unwrap_or(u32::MAX),
+            focal_length: 0.0,
+            exposure: args.exposure,
+            tonemap: args.tonemap as u32,
+            gamma: match args.gamma {
+                Some(gamma) if !base.surface_config.format.is_srgb() => gamma,
+                Some(_) => {
+                    log::info!("Ignoring gamma, the surface format is already sRGB");
+                    1.0
+                }
+                None => 1.0,
+            },
+            _padding: [0; 2],
+            camera: [[0.0; 4]; 4],
         };
         let locals_buffer = base
             .device
@@ -460,6 +1677,53 @@ impl Subject {
                     ],
                 });
 
+        let bind_group =
+            Self::create_bind_group(base, &bind_group_layout, &locals_buffer, &rng_view);
+
+        Self {
+            locals,
+            locals_buffer,
+            seed,
+            shuffle_rng,
+            _rng: rng,
+            _rng_view: rng_view,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    fn create_rng(
+        base: &Base,
+        seed: u64,
+        width: u32,
+        height: u32,
+    ) -> (wgpu::Texture, wgpu::TextureView) {
+        let rng_texture_data: Vec<[u32; 4]> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| pixel_rng_state(seed, x, y)))
+            .collect();
+
+        let rng = base.device.create_texture_with_data(
+            &base.queue,
+            &wgpu::TextureDescriptor {
+                label: None,
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba32Uint,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[wgpu::TextureFormat::Rgba32Uint],
+            },
+            <_>::default(),
+            bytemuck::cast_slice(&rng_texture_data),
+        );
+
+        drop(rng_texture_data);
+
         let rng_view = rng.create_view(&wgpu::TextureViewDescriptor {
             label: None,
             format: Some(wgpu::TextureFormat::Rgba32Uint),
@@ -467,33 +1731,62 @@ impl Subject {
             aspect: wgpu::TextureAspect::All,
             ..<_>::default()
         });
-        let bind_group = base.device.create_bind_group(&wgpu::BindGroupDescriptor {
+
+        (rng, rng_view)
+    }
+
+    fn create_bind_group(
+        base: &Base,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        locals_buffer: &wgpu::Buffer,
+        rng_view: &wgpu::TextureView,
+    ) -> wgpu::BindGroup {
+        base.device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("subject"),
-            layout: &bind_group_layout,
+            layout: bind_group_layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
                     resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                        buffer: &locals_buffer,
+                        buffer: locals_buffer,
                         offset: 0,
                         size: Some(NonZeroU64::new(mem::size_of::<Locals>() as u64).unwrap()),
                     }),
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
-                    resource: wgpu::BindingResource::TextureView(&rng_view),
+                    resource: wgpu::BindingResource::TextureView(rng_view),
                 },
             ],
-        });
+        })
+    }
 
-        Self {
-            locals,
-            locals_buffer,
-            _rng: rng,
-            _rng_view: rng_view,
-            bind_group_layout,
-            bind_group,
-        }
+    /// Recreates per-pixel random number generators for a new resolution
+    fn resize(&mut self, base: &Base, width: u32, height: u32) {
+        let (rng, rng_view) = Self::create_rng(base, self.seed, width, height);
+        self.bind_group = Self::create_bind_group(
+            base,
+            &self.bind_group_layout,
+            &self.locals_buffer,
+            &rng_view,
+        );
+        self._rng = rng;
+        self._rng_view = rng_view;
+        self.locals.shape = [width, height];
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reseed(&mut self, base: &Base, seed: u64) {
+        self.seed = seed;
+        self.shuffle_rng = rand_xoshiro::Xoshiro128PlusPlus::seed_from_u64(seed);
+        let [width, height] = self.locals.shape;
+        self.resize(base, width, height);
+    }
+
+    /// Doesn't update the buffer
+    fn set_camera(&mut self, camera: &Camera) {
+        self.locals.focal_length = camera.focal_length();
+        self.locals.camera = camera.frame().map(|[x, y, z]| [x, y, z, 0.0]);
     }
 
     fn update_locals_buffer(&mut self, base: &Base) {
@@ -502,6 +1795,16 @@ impl Subject {
     }
 }
 
+/// Xoshiro128+ state of a pixel, depends only on the seed and pixel coordinates
+fn pixel_rng_state(seed: u64, x: u32, y: u32) -> [u32; 4] {
+    let coords = u64::from(y) << 32 | u64::from(x);
+    let seed = rand_xoshiro::SplitMix64::seed_from_u64(seed).next_u64();
+    let mut seed_rng = rand_xoshiro::SplitMix64::seed_from_u64(seed ^ coords);
+    std::iter::repeat_with(|| seed_rng.gen())
+        .find(|s| s != &[0; 4])
+        .unwrap()
+}
+
 struct DoubleFramebuffers {
     bind_group_layout: wgpu::BindGroupLayout,
     max_framebuffer_weight: f32,
@@ -529,8 +1832,8 @@ impl DoubleFramebuffers {
                     }],
                 });
         DoubleFramebuffers {
-            target: Framebuffer::new(base, args, &bind_group_layout, format),
-            secondary: Framebuffer::new(base, args, &bind_group_layout, format),
+            target: Framebuffer::new(base, args.width, args.height, &bind_group_layout, format),
+            secondary: Framebuffer::new(base, args.width, args.height, &bind_group_layout, format),
             bind_group_layout,
             max_framebuffer_weight: args.max_framebuffer_weight,
             format,
@@ -540,10 +1843,16 @@ impl DoubleFramebuffers {
     fn swap(&mut self) {
         mem::swap(&mut self.target, &mut self.secondary)
     }
+
+    fn resize(&mut self, base: &Base, width: u32, height: u32) {
+        for fb in [&mut self.target, &mut self.secondary] {
+            *fb = Framebuffer::new(base, width, height, &self.bind_group_layout, self.format);
+        }
+    }
 }
 
 struct Framebuffer {
-    _fb: wgpu::Texture,
+    fb: wgpu::Texture,
     fb_view: wgpu::TextureView,
     bind_group: wgpu::BindGroup,
 }
@@ -551,22 +1860,25 @@ struct Framebuffer {
 impl Framebuffer {
     fn new(
         base: &Base,
-        args: &Args,
+        width: u32,
+        height: u32,
         bind_group_layout: &wgpu::BindGroupLayout,
         format: wgpu::TextureFormat,
     ) -> Self {
         let fb = base.device.create_texture(&wgpu::TextureDescriptor {
             label: None,
             size: wgpu::Extent3d {
-                width: args.width,
-                height: args.height,
+                width,
+                height,
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
             view_formats: &[format],
         });
 
@@ -587,13 +1899,17 @@ impl Framebuffer {
         });
 
         Self {
-            _fb: fb,
+            fb,
             fb_view,
             bind_group,
         }
     }
 }
 
+/// Data arrays wrap into rows of this many texels, the smallest `max_texture_dimension_2d`
+/// of any backend, so they only fail to fit scenes rejected by [`World::parse`]
+pub(crate) const DATA_TEXTURE_WIDTH: usize = 2048;
+
 struct Object {
     _base_indices: wgpu::Buffer,
     _data_vec4_f32: wgpu::Texture,
@@ -607,37 +1923,7 @@ struct Object {
 }
 
 impl Object {
-    fn new(base: &Base, _: &Args) -> Self {
-        mod api {
-            #[derive(Clone, Copy)]
-            pub struct Lambertian {
-                pub albedo: [f32; 3],
-            }
-
-            #[derive(Clone, Copy)]
-            pub struct Metal {
-                pub albedo: [f32; 3],
-                pub fuzz: f32,
-            }
-
-            #[derive(Clone, Copy)]
-            pub enum DynMaterial {
-                Lambertian(Lambertian),
-                Metal(Metal),
-            }
-
-            #[derive(Clone, Copy)]
-            pub struct Sphere {
-                pub center: [f32; 3],
-                pub radius: f32,
-                pub material: DynMaterial,
-            }
-
-            pub struct World {
-                pub spheres: Vec<Sphere>,
-            }
-        }
-
+    fn new(base: &Base, world: &World) -> Self {
         mod raw {
             use bytemuck::{Pod, Zeroable};
 
@@ -684,41 +1970,6 @@ impl Object {
             }
         }
 
-        let world = api::World {
-            spheres: vec![
-                api::Sphere {
-                    center: [0., -100.5, -1.],
-                    radius: 100.,
-                    material: api::DynMaterial::Lambertian(api::Lambertian {
-                        albedo: [0.8, 0.8, 0.],
-                    }),
-                },
-                api::Sphere {
-                    center: [0., 0., -1.],
-                    radius: 0.5,
-                    material: api::DynMaterial::Lambertian(api::Lambertian {
-                        albedo: [0.7, 0.3, 0.3],
-                    }),
-                },
-                api::Sphere {
-                    center: [-1., 0., -1.],
-                    radius: 0.5,
-                    material: api::DynMaterial::Metal(api::Metal {
-                        albedo: [0.8, 0.8, 0.8],
-                        fuzz: 0.3,
-                    }),
-                },
-                api::Sphere {
-                    center: [1., 0., -1.],
-                    radius: 0.5,
-                    material: api::DynMaterial::Metal(api::Metal {
-                        albedo: [0.8, 0.6, 0.2],
-                        fuzz: 1.0,
-                    }),
-                },
-            ],
-        };
-
         let mut sphere_centers = Vec::new();
         let mut sphere_radiuses = Vec::new();
         let mut sphere_material_idxs = Vec::new();
@@ -733,12 +1984,12 @@ impl Object {
             sphere_radiuses.push(sphere.radius);
             let material_idx;
             match sphere.material {
-                api::DynMaterial::Lambertian(api::Lambertian { albedo }) => {
+                Material::Lambertian(Lambertian { albedo }) => {
                     sphere_material_tys.push(raw::MaterialTy::Lambertian as i32);
                     material_idx = lambertian_albedos.len() as i32;
                     lambertian_albedos.push(albedo);
                 }
-                api::DynMaterial::Metal(api::Metal { albedo, fuzz }) => {
+                Material::Metal(Metal { albedo, fuzz }) => {
                     sphere_material_tys.push(raw::MaterialTy::Metal as i32);
                     material_idx = metal_albedos.len() as i32;
                     metal_albedos.push(albedo);
@@ -765,6 +2016,18 @@ impl Object {
             base_idx as i32
         }
 
+        /// Pads the data to whole rows of [`DATA_TEXTURE_WIDTH`]
+        fn wrap<T: Copy + Zeroable>(data: &mut Vec<T>) -> wgpu::Extent3d {
+            let width = data.len().min(DATA_TEXTURE_WIDTH);
+            let height = data.len().div_ceil(DATA_TEXTURE_WIDTH);
+            data.resize(width * height, T::zeroed());
+            wgpu::Extent3d {
+                width: width as u32,
+                height: height as u32,
+                depth_or_array_layers: 1,
+            }
+        }
+
         let raw_world = raw::World {
             spheres: raw::SphereRange {
                 center_base_idx: push(
@@ -808,15 +2071,11 @@ impl Object {
 
         let vec4_f32_data_tex_desc = wgpu::TextureDescriptor {
             label: Some("vec4_f32_data"),
-            size: wgpu::Extent3d {
-                width: vec4_f32_data.len() as u32,
-                height: 1,
-                depth_or_array_layers: 1,
-            },
+            size: wrap(&mut vec4_f32_data),
             format: wgpu::TextureFormat::Rgba32Float,
             mip_level_count: 1,
             sample_count: 1,
-            dimension: wgpu::TextureDimension::D1,
+            dimension: wgpu::TextureDimension::D2,
             usage: wgpu::TextureUsages::TEXTURE_BINDING,
             view_formats: &[wgpu::TextureFormat::Rgba32Float],
         };
@@ -832,11 +2091,7 @@ impl Object {
             &base.queue,
             &wgpu::TextureDescriptor {
                 label: Some("f32_data"),
-                size: wgpu::Extent3d {
-                    width: f32_data.len() as u32,
-                    height: 1,
-                    depth_or_array_layers: 1,
-                },
+                size: wrap(&mut f32_data),
                 format: wgpu::TextureFormat::R32Float,
                 view_formats: &[wgpu::TextureFormat::R32Float],
                 ..vec4_f32_data_tex_desc
@@ -849,11 +2104,7 @@ impl Object {
             &base.queue,
             &wgpu::TextureDescriptor {
                 label: Some("i32_data"),
-                size: wgpu::Extent3d {
-                    width: i32_data.len() as u32,
-                    height: 1,
-                    depth_or_array_layers: 1,
-                },
+                size: wrap(&mut i32_data),
                 format: wgpu::TextureFormat::R32Sint,
                 view_formats: &[wgpu::TextureFormat::R32Sint],
                 ..vec4_f32_data_tex_desc
@@ -884,7 +2135,7 @@ impl Object {
                             visibility: wgpu::ShaderStages::FRAGMENT,
                             ty: wgpu::BindingType::Texture {
                                 sample_type: wgpu::TextureSampleType::Float { filterable: false },
-                                view_dimension: wgpu::TextureViewDimension::D1,
+                                view_dimension: wgpu::TextureViewDimension::D2,
                                 multisampled: false,
                             },
                             count: None,
@@ -894,7 +2145,7 @@ impl Object {
                             visibility: wgpu::ShaderStages::FRAGMENT,
                             ty: wgpu::BindingType::Texture {
                                 sample_type: wgpu::TextureSampleType::Float { filterable: false },
-                                view_dimension: wgpu::TextureViewDimension::D1,
+                                view_dimension: wgpu::TextureViewDimension::D2,
                                 multisampled: false,
                             },
                             count: None,
@@ -904,7 +2155,7 @@ impl Object {
                             visibility: wgpu::ShaderStages::FRAGMENT,
                             ty: wgpu::BindingType::Texture {
                                 sample_type: wgpu::TextureSampleType::Sint,
-                                view_dimension: wgpu::TextureViewDimension::D1,
+                                view_dimension: wgpu::TextureViewDimension::D2,
                                 multisampled: false,
                             },
                             count: None,
@@ -915,21 +2166,21 @@ impl Object {
         let view_vec4_f32 = data_vec4_f32.create_view(&wgpu::TextureViewDescriptor {
             label: Some("vec4_f32_data"),
             format: Some(wgpu::TextureFormat::Rgba32Float),
-            dimension: Some(wgpu::TextureViewDimension::D1),
+            dimension: Some(wgpu::TextureViewDimension::D2),
             aspect: wgpu::TextureAspect::All,
             ..<_>::default()
         });
         let view_f32 = data_f32.create_view(&wgpu::TextureViewDescriptor {
             label: Some("f32_data"),
             format: Some(wgpu::TextureFormat::R32Float),
-            dimension: Some(wgpu::TextureViewDimension::D1),
+            dimension: Some(wgpu::TextureViewDimension::D2),
             aspect: wgpu::TextureAspect::All,
             ..<_>::default()
         });
         let view_i32 = data_i32.create_view(&wgpu::TextureViewDescriptor {
             label: Some("i32_data"),
             format: Some(wgpu::TextureFormat::R32Sint),
-            dimension: Some(wgpu::TextureViewDimension::D1),
+            dimension: Some(wgpu::TextureViewDimension::D2),
             aspect: wgpu::TextureAspect::All,
             ..<_>::default()
         });
@@ -0,0 +1,60 @@
+use crate::{Args, BackendInfo, Base, Image, InitError, Param, Progress, State, World};
+use futures_channel::oneshot;
+use std::future::Future;
+
+/// Renders into an `OffscreenCanvas` without an event loop, e.g. inside of a worker
+///
+/// Unlike [`App`](crate::App) it doesn't follow size changes, `args.width` and
+/// `args.height` must be the physical size of the canvas.
+pub struct OffscreenApp {
+    state: State,
+}
+
+impl OffscreenApp {
+    pub async fn new(
+        canvas: web_sys::OffscreenCanvas,
+        args: Args,
+        world: &World,
+    ) -> Result<Self, InitError> {
+        canvas.set_width(args.width);
+        canvas.set_height(args.height);
+        let target = wgpu::SurfaceTarget::OffscreenCanvas(canvas);
+        let base = Base::new(Some(target), None, &args).await?;
+        Ok(OffscreenApp {
+            state: State::from_base(base, &args, world),
+        })
+    }
+
+    /// Accumulates a frame and presents the result, call it once per animation frame
+    pub fn redraw(&mut self) {
+        self.state.redraw();
+    }
+
+    /// Whether `max_samples` have been accumulated, further redraws only present
+    pub fn is_converged(&self) -> bool {
+        self.state.is_converged()
+    }
+
+    /// Changes a rendering parameter, invalid values are ignored with an error logged
+    ///
+    /// Redraw afterwards to present the change even if converged. [`Param::TargetFps`] is
+    /// ignored, as redraws are driven by the caller.
+    pub fn set_param(&mut self, param: Param) {
+        self.state.set_param(param);
+    }
+
+    pub fn backend_info(&self) -> &BackendInfo {
+        &self.state.base.backend_info
+    }
+
+    pub fn progress(&self) -> Progress {
+        self.state.progress()
+    }
+
+    /// Reads back the latest accumulated image
+    pub fn capture(&self) -> impl Future<Output = Option<Image>> {
+        let (sender, receiver) = oneshot::channel();
+        self.state.capture(vec![sender]);
+        async move { receiver.await.ok() }
+    }
+}
@@ -11,9 +11,17 @@ struct Locals {
     depth: u32,
     rng_shuffle: vec4<u32>,
     weight_framebuffer: f32,
-    _padding1: i32,
-    _padding2: i32,
-    _padding3: i32,
+    rr_start_depth: u32,
+    focal_length: f32,
+    exposure: f32,
+    tonemap: u32,
+    gamma: f32,
+    _padding2: u32,
+    _padding3: u32,
+    camera_position: vec4<f32>,
+    camera_right: vec4<f32>,
+    camera_up: vec4<f32>,
+    camera_backward: vec4<f32>,
 }
 
 @group(0) @binding(0)
@@ -29,13 +37,49 @@ fn vs_main(@location(0) vertex: vec2<f32>) -> VertexOutput {
 @group(1) @binding(0)
 var r_framebuffer: texture_2d<f32>;
 
+fn framebuffer_texel(texel: vec2<i32>) -> vec4<f32> {
+    let texel_clamped = clamp(texel, vec2<i32>(0), r_locals.shape - vec2<i32>(1));
+    return textureLoad(r_framebuffer, texel_clamped, 0);
+}
+
+// Bilinear by hand, as float textures aren't filterable everywhere.
+// Exact at texel centers, so an unscaled framebuffer is copied as is.
 fn framebuffer_load(pixel_pos: vec2<f32>) -> vec4<f32> {
-    let pixel_pos_clamped = clamp(vec2<i32>(pixel_pos), vec2<i32>(0), r_locals.shape - vec2<i32>(1));
-    return textureLoad(r_framebuffer, pixel_pos_clamped, 0);
+    let pos = pixel_pos - vec2<f32>(0.5);
+    let origin = floor(pos);
+    let t = pos - origin;
+    let texel = vec2<i32>(origin);
+    let top = mix(framebuffer_texel(texel), framebuffer_texel(texel + vec2<i32>(1, 0)), t.x);
+    let bottom = mix(framebuffer_texel(texel + vec2<i32>(0, 1)), framebuffer_texel(texel + vec2<i32>(1, 1)), t.x);
+    return mix(top, bottom, t.y);
 }
 
+const TONEMAP_REINHARD: u32 = 1u;
+const TONEMAP_ACES: u32 = 2u;
+
+fn tonemap(color: vec3<f32>) -> vec3<f32> {
+    switch r_locals.tonemap {
+        case TONEMAP_REINHARD: {
+            return color / (vec3<f32>(1.0) + color);
+        }
+        case TONEMAP_ACES: {
+            // Krzysztof Narkowicz's fit
+            let a = color * (2.51 * color + 0.03);
+            let b = color * (2.43 * color + 0.59) + 0.14;
+            return clamp(a / b, vec3<f32>(0.0), vec3<f32>(1.0));
+        }
+        default: {
+            return color;
+        }
+    }
+}
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return framebuffer_load(in.pixel_pos);
+    let color = framebuffer_load(in.pixel_pos);
+    var mapped = tonemap(r_locals.exposure * color.rgb);
+    if r_locals.gamma != 1.0 {
+        mapped = pow(max(mapped, vec3<f32>(0.0)), vec3<f32>(1.0 / r_locals.gamma));
+    }
+    return vec4<f32>(mapped, color.a);
 }
@@ -0,0 +1,215 @@
+use crate::{error::SceneError, DATA_TEXTURE_WIDTH};
+use rand::Rng;
+use rand_xoshiro::rand_core::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+/// Objects to render, the camera looks from the origin towards `-z`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct World {
+    pub spheres: Vec<Sphere>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Sphere {
+    pub center: [f32; 3],
+    pub radius: f32,
+    pub material: Material,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Material {
+    Lambertian(Lambertian),
+    Metal(Metal),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Lambertian {
+    pub albedo: [f32; 3],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Metal {
+    pub albedo: [f32; 3],
+    pub fuzz: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SceneFormat {
+    Ron,
+    Json,
+}
+
+impl SceneFormat {
+    /// Guesses the format from the file extension, defaulting to RON
+    pub fn from_path(path: &str) -> Self {
+        if path.to_ascii_lowercase().ends_with(".json") {
+            SceneFormat::Json
+        } else {
+            SceneFormat::Ron
+        }
+    }
+}
+
+impl World {
+    /// Names accepted by [`World::builtin`]
+    pub const BUILTINS: &'static [&'static str] = &["default", "weekend-final"];
+
+    /// Returns one of the scenes shipped with the renderer
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(World::default()),
+            "weekend-final" => Some(World::weekend_final()),
+            _ => None,
+        }
+    }
+
+    /// Formats the world the way [`World::parse`] reads it
+    pub fn to_string(&self, format: SceneFormat) -> String {
+        match format {
+            SceneFormat::Ron => ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+                .expect("serializing world into RON"),
+            SceneFormat::Json => {
+                serde_json::to_string_pretty(self).expect("serializing world into JSON")
+            }
+        }
+    }
+
+    pub fn parse(source: &str, format: SceneFormat) -> Result<Self, SceneError> {
+        let world: World = match format {
+            SceneFormat::Ron => ron::from_str(source).map_err(SceneError::new)?,
+            SceneFormat::Json => serde_json::from_str(source).map_err(SceneError::new)?,
+        };
+        world.validate()?;
+        Ok(world)
+    }
+
+    /// Checks what the GPU upload can't cope with
+    fn validate(&self) -> Result<(), SceneError> {
+        if self.spheres.is_empty() {
+            return Err(SceneError::new("scene has no spheres"));
+        }
+        // A sphere takes up to two texels of each data texture, which can't be taller than wide
+        // on backends with the smallest textures
+        let max_spheres = DATA_TEXTURE_WIDTH * DATA_TEXTURE_WIDTH / 2;
+        if self.spheres.len() > max_spheres {
+            return Err(SceneError::new(format!(
+                "scene has {} spheres, at most {max_spheres} fit into GPU textures",
+                self.spheres.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl World {
+    /// Final scene of "Ray Tracing in One Weekend" without glass, fitted in front of the camera
+    ///
+    /// Small spheres are placed by a fixed seed, so it's the same on every run.
+    fn weekend_final() -> Self {
+        let mut rng = rand_xoshiro::Xoshiro128PlusPlus::seed_from_u64(0);
+        let mut spheres = vec![Sphere {
+            center: [0., -1000.5, -1.],
+            radius: 1000.,
+            material: Material::Lambertian(Lambertian {
+                albedo: [0.5, 0.5, 0.5],
+            }),
+        }];
+        let big = [[-1.1, 0., -2.], [0., 0., -2.5], [1.1, 0., -2.]];
+        for a in -5..=5 {
+            for b in -10..=-1 {
+                let center = [
+                    a as f32 * 0.4 + rng.gen::<f32>() * 0.3,
+                    -0.4,
+                    b as f32 * 0.4 + rng.gen::<f32>() * 0.3,
+                ];
+                let clear =
+                    |c: &[f32; 3]| (c[0] - center[0]).hypot(c[2] - center[2]) > 0.5 + 0.1 + 0.05;
+                if !big.iter().all(clear) {
+                    continue;
+                }
+                let material = if rng.gen::<f32>() < 0.8 {
+                    let [x, y] = [rng.gen::<[f32; 3]>(), rng.gen::<[f32; 3]>()];
+                    Material::Lambertian(Lambertian {
+                        albedo: [0, 1, 2].map(|i| x[i] * y[i]),
+                    })
+                } else {
+                    Material::Metal(Metal {
+                        albedo: rng.gen::<[f32; 3]>().map(|c| 0.5 + c * 0.5),
+                        fuzz: rng.gen::<f32>() * 0.5,
+                    })
+                };
+                spheres.push(Sphere {
+                    center,
+                    radius: 0.1,
+                    material,
+                });
+            }
+        }
+        let [left, middle, right] = big;
+        spheres.extend([
+            Sphere {
+                center: left,
+                radius: 0.5,
+                material: Material::Lambertian(Lambertian {
+                    albedo: [0.4, 0.2, 0.1],
+                }),
+            },
+            Sphere {
+                center: middle,
+                radius: 0.5,
+                material: Material::Metal(Metal {
+                    albedo: [0.8, 0.8, 0.8],
+                    fuzz: 0.0,
+                }),
+            },
+            Sphere {
+                center: right,
+                radius: 0.5,
+                material: Material::Metal(Metal {
+                    albedo: [0.7, 0.6, 0.5],
+                    fuzz: 0.0,
+                }),
+            },
+        ]);
+        World { spheres }
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        World {
+            spheres: vec![
+                Sphere {
+                    center: [0., -100.5, -1.],
+                    radius: 100.,
+                    material: Material::Lambertian(Lambertian {
+                        albedo: [0.8, 0.8, 0.],
+                    }),
+                },
+                Sphere {
+                    center: [0., 0., -1.],
+                    radius: 0.5,
+                    material: Material::Lambertian(Lambertian {
+                        albedo: [0.7, 0.3, 0.3],
+                    }),
+                },
+                Sphere {
+                    center: [-1., 0., -1.],
+                    radius: 0.5,
+                    material: Material::Metal(Metal {
+                        albedo: [0.8, 0.8, 0.8],
+                        fuzz: 0.3,
+                    }),
+                },
+                Sphere {
+                    center: [1., 0., -1.],
+                    radius: 0.5,
+                    material: Material::Metal(Metal {
+                        albedo: [0.8, 0.6, 0.2],
+                        fuzz: 1.0,
+                    }),
+                },
+            ],
+        }
+    }
+}
@@ -11,9 +11,17 @@ struct Locals {
     depth: u32,
     rng_shuffle: vec4<u32>,
     framebuffer_weight: f32,
-    _padding1: i32,
-    _padding2: i32,
-    _padding3: i32,
+    rr_start_depth: u32,
+    focal_length: f32,
+    exposure: f32,
+    tonemap: u32,
+    gamma: f32,
+    _padding2: u32,
+    _padding3: u32,
+    camera_position: vec4<f32>,
+    camera_right: vec4<f32>,
+    camera_up: vec4<f32>,
+    camera_backward: vec4<f32>,
 }
 
 @group(0) @binding(0)
@@ -187,17 +195,24 @@ var<uniform> r_world: World;
 // Data arrays
 
 @group(1) @binding(1)
-var r_vec4_f32_data: texture_1d<f32>;
+var r_vec4_f32_data: texture_2d<f32>;
 
 @group(1) @binding(2)
-var r_f32_data: texture_1d<f32>;
+var r_f32_data: texture_2d<f32>;
 
 @group(1) @binding(3)
-var r_i32_data: texture_1d<i32>;
+var r_i32_data: texture_2d<i32>;
+
+// Same as `DATA_TEXTURE_WIDTH`, the arrays wrap into rows of it
+const DATA_TEXTURE_WIDTH: i32 = 2048;
+
+fn data_coords(data_idx: i32) -> vec2<i32> {
+    return vec2<i32>(data_idx % DATA_TEXTURE_WIDTH, data_idx / DATA_TEXTURE_WIDTH);
+}
 
 fn lambertian_load_albedo(idx: i32) -> vec3<f32> {
     let data_idx = r_world.lambertians.albedo_base_idx + idx;
-    return textureLoad(r_vec4_f32_data, data_idx, 0).xyz;
+    return textureLoad(r_vec4_f32_data, data_coords(data_idx), 0).xyz;
 }
 
 fn lambertian_scatter(idx: i32, rng: ptr<function, Xoshiro128Plus>, args: ptr<function, ScatterArgs>, out: ptr<function, ScatterOutput>) -> bool {
@@ -217,12 +232,12 @@ fn lambertian_scatter(idx: i32, rng: ptr<function, Xoshiro128Plus>, args: ptr<fu
 
 fn metal_load_albedo(idx: i32) -> vec3<f32> {
     let data_idx = r_world.metals.albedo_base_idx + idx;
-    return textureLoad(r_vec4_f32_data, data_idx, 0).xyz;
+    return textureLoad(r_vec4_f32_data, data_coords(data_idx), 0).xyz;
 }
 
 fn metal_load_fuzz(idx: i32) -> f32 {
     let data_idx = r_world.metals.fuzz_base_idx + idx;
-    return textureLoad(r_f32_data, data_idx, 0).x;
+    return textureLoad(r_f32_data, data_coords(data_idx), 0).x;
 }
 
 fn metal_scatter(idx: i32, rng: ptr<function, Xoshiro128Plus>, args: ptr<function, ScatterArgs>, out: ptr<function, ScatterOutput>) -> bool {
@@ -253,18 +268,18 @@ fn dyn_material_scatter(m: DynMaterial, rng: ptr<function, Xoshiro128Plus>, args
 
 fn sphere_load_center(idx: i32) -> vec3<f32> {
     let data_idx = r_world.spheres.center_base_idx + idx;
-    return textureLoad(r_vec4_f32_data, data_idx, 0).xyz;
+    return textureLoad(r_vec4_f32_data, data_coords(data_idx), 0).xyz;
 }
 
 fn sphere_load_radius(idx: i32) -> f32 {
     let data_idx = r_world.spheres.radius_base_idx + idx;
-    return textureLoad(r_f32_data, data_idx, 0).x;
+    return textureLoad(r_f32_data, data_coords(data_idx), 0).x;
 }
 
 fn sphere_load_material(idx: i32) -> DynMaterial {
     let type_idx = r_world.spheres.material_ty_base_idx + idx;
     let idx_idx = r_world.spheres.material_idx_base_idx + idx;
-    return DynMaterial(textureLoad(r_i32_data, type_idx, 0).x, textureLoad(r_i32_data, idx_idx, 0).x);
+    return DynMaterial(textureLoad(r_i32_data, data_coords(type_idx), 0).x, textureLoad(r_i32_data, data_coords(idx_idx), 0).x);
 }
 
 fn sphere_hit(idx: i32, args: ptr<function, HitArgs>, out: ptr<function, Hit>) -> bool {
@@ -337,6 +352,16 @@ fn color_world(ray_norm: Ray, rng: ptr<function, Xoshiro128Plus>) -> vec3<f32> {
     var result: ScatterOutput = ScatterOutput(vec3<f32>(1.0), ray_norm);
     
     for (var i: u32 = r_locals.depth; i > 0u; i = i - 1u) {
+        // Russian roulette: survive with probability of the throughput's max component
+        if (r_locals.depth - i >= r_locals.rr_start_depth) {
+            let throughput = result.attenuation;
+            let survival = min(max(throughput.x, max(throughput.y, throughput.z)), 1.0);
+            if (xoshiro128plus_random_f32(rng) >= survival) {
+                return vec3<f32>(0.0);
+            }
+            result.attenuation = throughput / survival;
+        }
+
         var hit_args: HitArgs = HitArgs(result.ray, 0.001, 1.0e4);
         var hit: Hit = hit_nil();
         
@@ -357,9 +382,6 @@ fn color_world(ray_norm: Ray, rng: ptr<function, Xoshiro128Plus>) -> vec3<f32> {
     return vec3<f32>(0.0);
 }
 
-const FOCAL_LENGTH: f32 = 1.0;
-const ORIGIN: vec3<f32> = vec3<f32>(0.0, 0.0, 0.0);
-
 @group(2) @binding(0)
 var r_framebuffer: texture_2d<f32>;
 
@@ -375,10 +397,12 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
 
     var color: vec3<f32> = vec3<f32>(0.0);
     var rng: Xoshiro128Plus = xoshiro128plus_load(in.pixel_pos);
+    let origin = r_locals.camera_position.xyz;
     for (var i: u32 = 0u; i < r_locals.sample_count; i = i + 1u) {
         let sample_offset = xoshiro128plus_random_vec2_f32(&rng) * pixel_side;
         let viewport = viewport_base + sample_offset;
-        color = color + color_world(Ray(ORIGIN, normalize(vec3<f32>(viewport, -FOCAL_LENGTH) - ORIGIN)), &rng);
+        let dir = viewport.x * r_locals.camera_right.xyz + viewport.y * r_locals.camera_up.xyz - r_locals.focal_length * r_locals.camera_backward.xyz;
+        color = color + color_world(Ray(origin, normalize(dir)), &rng);
     }
     color = color / f32(r_locals.sample_count);
     
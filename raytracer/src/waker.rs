@@ -1,49 +1,37 @@
 use std::{sync::Arc, task};
 
-use winit::event_loop::EventLoopProxy;
-
-use crate::AppEvent;
-
-pub type AppEventDispatchWaker = EventDispatchWaker<AppEvent>;
+use crate::{AppEvent, AppEventDispatch};
 
+/// Wakes a future by sending an event to the app polling it
 #[derive(Clone)]
-pub struct EventDispatchWaker<T: 'static> {
-    dispatch: EventLoopProxy<T>,
-    event: T,
+pub(crate) struct AppEventDispatchWaker {
+    dispatch: AppEventDispatch,
+    event: AppEvent,
 }
 
-impl<T: 'static> EventDispatchWaker<T> {
-    pub fn new(dispatch: EventLoopProxy<T>, event: T) -> Self {
+impl AppEventDispatchWaker {
+    pub(crate) fn new(dispatch: AppEventDispatch, event: AppEvent) -> Self {
         Self { dispatch, event }
     }
 
-    pub fn into_waker(self) -> task::Waker
-    where
-        T: Clone + Send + Sync,
-    {
+    pub(crate) fn into_waker(self) -> task::Waker {
         Arc::new(self).into()
     }
 }
 
-impl<T> From<EventDispatchWaker<T>> for task::Waker
-where
-    T: Clone + Send + Sync + 'static,
-{
+impl From<AppEventDispatchWaker> for task::Waker {
     #[inline(always)]
-    fn from(value: EventDispatchWaker<T>) -> Self {
+    fn from(value: AppEventDispatchWaker) -> Self {
         value.into_waker()
     }
 }
 
-impl<T> task::Wake for EventDispatchWaker<T>
-where
-    T: Clone + 'static,
-{
+impl task::Wake for AppEventDispatchWaker {
     fn wake(self: Arc<Self>) {
         self.wake_by_ref()
     }
 
     fn wake_by_ref(self: &Arc<Self>) {
-        let _ = self.dispatch.send_event(self.event.clone());
+        self.dispatch.send_event(self.event);
     }
 }
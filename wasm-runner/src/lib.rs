@@ -1,10 +1,80 @@
-use raytracer::{
-    winit::{event_loop::EventLoop, platform::web::EventLoopExtWebSys},
-    App,
+use raytracer::winit::{event_loop::EventLoop, platform::web::EventLoopExtWebSys};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize as _, Serializer};
+use std::{
+    cell::{Cell, RefCell},
+    fmt::Display,
+    rc::Rc,
+    str::FromStr,
+    time::Duration,
 };
 use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
 
-#[derive(serde::Deserialize, Clone, Copy, Debug)]
+mod logger;
+mod visibility;
+mod worker;
+
+/// Minimal interval between progress callback invocations
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+thread_local! {
+    /// Main thread apps share the event loop, winit allows only one per page
+    static APP_GROUP: RefCell<Option<raytracer::AppGroupHandle>> = const { RefCell::new(None) };
+}
+
+/// Returns the group of main thread apps, starting its event loop on the first call
+fn app_group() -> Result<raytracer::AppGroupHandle, JsValue> {
+    APP_GROUP.with_borrow_mut(|group| {
+        if let Some(group) = group {
+            return Ok(group.clone());
+        }
+        let event_loop = EventLoop::with_user_event().build().map_err(|e| {
+            ErrorKind::EventLoop.error(&format!("Failed to build an event loop: {e}"))
+        })?;
+        let app_group = raytracer::AppGroup::new(&event_loop);
+        let handle = group.insert(app_group.handle()).clone();
+        event_loop.spawn_app(app_group);
+        Ok(handle)
+    })
+}
+
+/// `kind` of errors raised by the runner itself, see [`raytracer::InitErrorKind`] for the rest
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ErrorKind {
+    /// A query string parameter failed to parse
+    BadQueryParam,
+    /// Another logger is already installed
+    LoggerInit,
+    EventLoop,
+    /// `args` of [`spawn_app`] aren't an object of [`Args`] or have invalid values
+    InvalidArgs,
+    /// The scene couldn't be fetched or parsed
+    Scene,
+    /// The worker got a message it can't understand, e.g. from a mismatched module version
+    BadWorkerMessage,
+    /// The worker script failed to load or threw an uncaught exception
+    WorkerFailed,
+}
+
+impl ErrorKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::BadQueryParam => "bad_query_param",
+            ErrorKind::LoggerInit => "logger_init",
+            ErrorKind::EventLoop => "event_loop",
+            ErrorKind::InvalidArgs => "invalid_args",
+            ErrorKind::Scene => "scene",
+            ErrorKind::BadWorkerMessage => "bad_worker_message",
+            ErrorKind::WorkerFailed => "worker_failed",
+        }
+    }
+
+    fn error(self, message: &str) -> JsValue {
+        error_with_kind(message, self.as_str())
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
 #[serde(default)]
 pub struct Args {
     pub width: u32,
@@ -12,6 +82,43 @@ pub struct Args {
     pub sample_count: u32,
     pub ray_depth: u32,
     pub max_framebuffer_weight: f32,
+    pub rr_start_depth: Option<u32>,
+    /// Keep rendering at the initial resolution when the canvas is resized
+    pub lock_resolution: bool,
+    pub seed: Option<u64>,
+    pub max_samples: Option<u32>,
+    /// Vertical field of view in degrees
+    pub vfov: f32,
+    pub exposure: f32,
+    /// One of `none`, `reinhard` or `aces`
+    #[serde(
+        deserialize_with = "deserialize_from_str",
+        serialize_with = "serialize_display"
+    )]
+    pub tonemap: raytracer::Tonemap,
+    /// One of `auto`, `fifo`, `mailbox` or `immediate`
+    #[serde(
+        deserialize_with = "deserialize_from_str",
+        serialize_with = "serialize_display"
+    )]
+    pub present_mode: raytracer::PresentMode,
+    pub resolution_scale: f32,
+    /// Either `webgpu` or `webgl`, initialization fails if it's unavailable
+    pub force_backend: Option<raytracer::GpuBackend>,
+    /// Device pixel ratio to render at, `window.devicePixelRatio` if unset
+    ///
+    /// The canvas keeps the display's ratio, the override scales `resolution_scale`.
+    pub dpr: Option<f64>,
+    /// Orbit the camera by dragging and dolly it by scrolling or pinching
+    pub camera_controls: bool,
+    /// URL of a RON or JSON scene file, chosen by the extension
+    pub scene: Option<String>,
+    /// URL of `worker.js` to render off the main thread, ignored if the browser can't
+    pub worker: Option<String>,
+    /// Pause while the page or the canvas isn't visible
+    pub auto_pause: bool,
+    /// Limit on frames per second while accumulating to save power, unlimited if zero
+    pub target_fps: f32,
 }
 
 impl From<Args> for raytracer::Args {
@@ -22,6 +129,23 @@ impl From<Args> for raytracer::Args {
             samples_per_frame: args.sample_count,
             ray_depth: args.ray_depth,
             max_framebuffer_weight: args.max_framebuffer_weight,
+            rr_start_depth: args.rr_start_depth,
+            seed: args.seed,
+            lock_resolution: args.lock_resolution,
+            max_samples: args.max_samples,
+            vfov: args.vfov,
+            exposure: args.exposure,
+            tonemap: args.tonemap,
+            gamma: None,
+            present_mode: args.present_mode,
+            resolution_scale: args.resolution_scale,
+            camera_controls: args.camera_controls,
+            backend: args.force_backend,
+            adapter: None,
+            fullscreen: false,
+            title: None,
+            stats: false,
+            target_fps: args.target_fps,
         }
     }
 }
@@ -34,6 +158,22 @@ impl From<raytracer::Args> for Args {
             sample_count: args.samples_per_frame,
             ray_depth: args.ray_depth,
             max_framebuffer_weight: args.max_framebuffer_weight,
+            rr_start_depth: args.rr_start_depth,
+            lock_resolution: args.lock_resolution,
+            seed: args.seed,
+            max_samples: args.max_samples,
+            vfov: args.vfov,
+            exposure: args.exposure,
+            tonemap: args.tonemap,
+            present_mode: args.present_mode,
+            resolution_scale: args.resolution_scale,
+            camera_controls: args.camera_controls,
+            force_backend: args.backend,
+            dpr: None,
+            scene: None,
+            worker: None,
+            auto_pause: true,
+            target_fps: args.target_fps,
         }
     }
 }
@@ -44,8 +184,29 @@ impl Default for Args {
     }
 }
 
+fn deserialize_from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let s = String::deserialize(deserializer)?;
+    s.parse().map_err(serde::de::Error::custom)
+}
+
+fn serialize_display<S, T>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Display,
+{
+    serializer.collect_str(value)
+}
+
+/// Installs the panic hook and the logger, runs when the module is initialized
+///
+/// Throws an `Error` with a `kind` of `bad_query_param` or `logger_init`.
 #[wasm_bindgen(start)]
-pub fn start() {
+pub fn start() -> Result<(), JsValue> {
     #[derive(serde::Deserialize, Clone, Copy, Debug)]
     #[serde(default)]
     struct QueryArgs {
@@ -61,34 +222,671 @@ pub fn start() {
     }
 
     std::panic::set_hook(Box::new(console_error_panic_hook::hook));
-    let query = query_string();
-    let args: QueryArgs = serde_urlencoded::from_str(&query).expect("Parsing query string");
-    console_log::init_with_level(args.log_level).expect("Initializing logger");
+    let args: QueryArgs = parse_query(&query_string())?;
+    let installed = logger::init(args.log_level).map_err(|e| {
+        ErrorKind::LoggerInit.error(&format!("Failed to initialize the logger: {e}"))
+    })?;
+    if !installed {
+        log::warn!("`start` was called again, keeping the logger");
+    }
     log::debug!("Parsed args from query: {args:?}");
+    Ok(())
+}
+
+/// Controls an app started by [`spawn_app`]
+///
+/// Methods are no-ops once the app has exited. Dropping the handle leaves the app running,
+/// but it no longer pauses while hidden.
+#[wasm_bindgen]
+pub struct AppHandle {
+    controls: Rc<Controls>,
+    _auto_pause: Option<visibility::AutoPause>,
+}
+
+/// Combines the explicit pause with the automatic one
+struct Controls {
+    backend: Backend,
+    paused: Cell<bool>,
+    /// The page or the canvas isn't visible
+    hidden: Cell<bool>,
+    /// Mirrors the app's exposure for PNG captures, unused with a worker
+    tonemapping: Cell<Tonemapping>,
+    progress: Rc<ProgressReporter>,
+}
+
+impl Controls {
+    fn update_pause(&self) {
+        if self.paused.get() || self.hidden.get() {
+            self.backend.pause();
+            self.progress.flush();
+        } else {
+            self.backend.resume();
+        }
+    }
+}
+
+enum Backend {
+    MainThread(raytracer::AppHandle),
+    Worker(worker::WorkerHandle),
+}
+
+impl Backend {
+    fn stop(&self) {
+        match self {
+            Backend::MainThread(app) => {
+                app.close();
+            }
+            Backend::Worker(worker) => worker.stop(),
+        }
+    }
+
+    fn pause(&self) {
+        match self {
+            Backend::MainThread(app) => {
+                app.pause();
+            }
+            Backend::Worker(worker) => worker.pause(),
+        }
+    }
+
+    fn resume(&self) {
+        match self {
+            Backend::MainThread(app) => {
+                app.resume();
+            }
+            Backend::Worker(worker) => worker.resume(),
+        }
+    }
+
+    fn set_param(&self, param: raytracer::Param) {
+        match self {
+            Backend::MainThread(app) => {
+                app.set_param(param);
+            }
+            Backend::Worker(worker) => worker.set_param(param),
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        match self {
+            Backend::MainThread(app) => app.is_running(),
+            Backend::Worker(worker) => worker.is_running(),
+        }
+    }
+
+    fn backend_info(&self) -> JsValue {
+        match self {
+            Backend::MainThread(app) => app
+                .backend_info()
+                .map(|info| {
+                    serde_wasm_bindgen::to_value(&BackendInfoEvent::from(info))
+                        .expect("Serializing backend info")
+                })
+                .unwrap_or_default(),
+            Backend::Worker(worker) => worker.backend_info(),
+        }
+    }
+
+    /// The worker tracks its own `tonemapping`, so it's only used on the main thread
+    fn capture(&self, format: CaptureFormat, tonemapping: Tonemapping) -> js_sys::Promise {
+        let app = match self {
+            Backend::MainThread(app) => app,
+            Backend::Worker(worker) => return worker.capture(format),
+        };
+        let capture = app.capture();
+        wasm_bindgen_futures::future_to_promise(async move {
+            let image = capture
+                .await
+                .ok_or_else(|| JsError::new("App is not running"))?;
+            format.encode(image, tonemapping)
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl AppHandle {
+    /// Stops rendering, dropping the surface and the GPU device
+    pub fn stop(&self) {
+        self.controls.backend.stop();
+        self.controls.progress.flush();
+    }
+
+    /// Pauses rendering until [`resume`](AppHandle::resume) even if the canvas is visible
+    pub fn pause(&self) {
+        self.controls.paused.set(true);
+        self.controls.update_pause();
+    }
+
+    pub fn resume(&self) {
+        self.controls.paused.set(false);
+        self.controls.update_pause();
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.controls.backend.is_running()
+    }
+
+    /// Whether [`pause`](AppHandle::pause) is in effect, ignoring pauses while hidden
+    pub fn is_paused(&self) -> bool {
+        self.controls.paused.get()
+    }
+
+    /// Whether rendering happens in a worker rather than on the main thread
+    pub fn in_worker(&self) -> bool {
+        matches!(self.controls.backend, Backend::Worker(_))
+    }
+
+    /// Returns `{ backend, name, driver, limits, maxTextureDimension2d }` describing the GPU
+    ///
+    /// `backend` is `BrowserWebGpu` for WebGPU and `Gl` for the WebGL2 fallback, which is
+    /// picked when the browser lacks WebGPU and the module is built with the `webgl` feature.
+    /// Browsers may leave `name` and `driver` empty.
+    pub fn backend_info(&self) -> JsValue {
+        self.controls.backend.backend_info()
+    }
+
+    /// Traces `n` samples per pixel each frame, up to 1024
+    pub fn set_samples_per_frame(&self, n: u32) -> Result<(), JsError> {
+        self.set_param(raytracer::Param::SamplesPerFrame(n))
+    }
+
+    /// Limits the number of bounces, up to 1024, discards accumulated samples
+    pub fn set_ray_depth(&self, n: u32) -> Result<(), JsError> {
+        self.set_param(raytracer::Param::RayDepth(n))
+    }
+
+    /// Sets the positive multiplier applied before tone mapping, keeps accumulated samples
+    pub fn set_exposure(&self, x: f32) -> Result<(), JsError> {
+        self.set_param(raytracer::Param::Exposure(x))
+    }
+
+    /// Caps the weight of accumulated samples between 0 and 1, lower values adapt faster
+    pub fn set_max_framebuffer_weight(&self, w: f32) -> Result<(), JsError> {
+        self.set_param(raytracer::Param::MaxFramebufferWeight(w))
+    }
+
+    /// Limits frames per second while accumulating, zero removes the limit
+    pub fn set_target_fps(&self, fps: f32) -> Result<(), JsError> {
+        self.set_param(raytracer::Param::TargetFps(fps))
+    }
+
+    /// Stops invoking the progress callback passed to [`spawn_app`]
+    pub fn unsubscribe_progress(&self) {
+        self.controls.progress.unsubscribe();
+    }
+
+    /// Resolves with a PNG `Blob` of the accumulated image
+    ///
+    /// Unlike the canvas' `toBlob` it reads the accumulation framebuffer,
+    /// so it doesn't depend on the swapchain contents. Exposure and tone mapping
+    /// are applied the same way as on the canvas.
+    pub fn capture_png(&self) -> js_sys::Promise {
+        self.capture(CaptureFormat::Png)
+    }
+
+    /// Resolves with `{ data, width, height }` of the accumulated linear radiance
+    ///
+    /// `data` is a `Float32Array` of RGBA pixels, top row first, without row padding.
+    /// Every call copies the framebuffer and waits for the GPU, so calling it every
+    /// frame noticeably slows down accumulation.
+    pub fn read_framebuffer(&self) -> js_sys::Promise {
+        self.capture(CaptureFormat::Raw)
+    }
+}
+
+impl AppHandle {
+    fn set_param(&self, param: raytracer::Param) -> Result<(), JsError> {
+        param.validate().map_err(|e| JsError::new(&e))?;
+        let mut tonemapping = self.controls.tonemapping.get();
+        tonemapping.update(param);
+        self.controls.tonemapping.set(tonemapping);
+        self.controls.backend.set_param(param);
+        Ok(())
+    }
+
+    fn capture(&self, format: CaptureFormat) -> js_sys::Promise {
+        self.controls
+            .backend
+            .capture(format, self.controls.tonemapping.get())
+    }
+}
+
+/// Exposure and tone mapping of the canvas, PNG captures apply them too
+#[derive(Clone, Copy, Debug)]
+struct Tonemapping {
+    exposure: f32,
+    tonemap: raytracer::Tonemap,
+}
+
+impl Tonemapping {
+    fn new(args: &Args) -> Self {
+        Tonemapping {
+            exposure: args.exposure,
+            tonemap: args.tonemap,
+        }
+    }
+
+    /// Follows parameter changes sent to the app
+    fn update(&mut self, param: raytracer::Param) {
+        if let raytracer::Param::Exposure(exposure) = param {
+            self.exposure = exposure;
+        }
+    }
+}
+
+/// How a captured image is handed to JS
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CaptureFormat {
+    /// A PNG `Blob`
+    Png,
+    /// `{ data, width, height }` with a `Float32Array`
+    Raw,
+}
+
+impl CaptureFormat {
+    fn as_str(self) -> &'static str {
+        match self {
+            CaptureFormat::Png => "png",
+            CaptureFormat::Raw => "raw",
+        }
+    }
+
+    fn from_str(s: &str) -> Option<Self> {
+        [CaptureFormat::Png, CaptureFormat::Raw]
+            .into_iter()
+            .find(|format| format.as_str() == s)
+    }
+
+    /// `tonemapping` only affects PNG, raw framebuffers stay linear
+    fn encode(
+        self,
+        mut image: raytracer::Image,
+        tonemapping: Tonemapping,
+    ) -> Result<JsValue, JsValue> {
+        match self {
+            CaptureFormat::Png => {
+                image.tonemap(tonemapping.exposure, tonemapping.tonemap);
+                Ok(png_blob(&image)?.into())
+            }
+            CaptureFormat::Raw => Ok(framebuffer_object(&image)),
+        }
+    }
+}
+
+fn framebuffer_object(image: &raytracer::Image) -> JsValue {
+    let data = js_sys::Float32Array::from(image.pixels.as_flattened());
+    let object = js_sys::Object::new();
+    for (key, value) in [
+        ("data", data.into()),
+        ("width", image.width.into()),
+        ("height", image.height.into()),
+    ] {
+        js_sys::Reflect::set(&object, &JsValue::from_str(key), &value)
+            .expect("Building a framebuffer object");
+    }
+    object.into()
+}
+
+fn png_blob(image: &raytracer::Image) -> Result<web_sys::Blob, JsValue> {
+    let png = js_sys::Uint8Array::from(image.to_png().as_slice());
+    web_sys::Blob::new_with_u8_array_sequence_and_options(
+        &js_sys::Array::of1(&png),
+        web_sys::BlobPropertyBag::new().type_("image/png"),
+    )
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgressEvent {
+    samples: u32,
+    width: u32,
+    height: u32,
+    elapsed_ms: f64,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BackendInfoEvent {
+    backend: String,
+    name: String,
+    driver: String,
+    limits: &'static str,
+    max_texture_dimension_2d: u32,
+}
+
+impl From<raytracer::BackendInfo> for BackendInfoEvent {
+    fn from(info: raytracer::BackendInfo) -> Self {
+        BackendInfoEvent {
+            backend: info.backend,
+            name: info.name,
+            driver: info.driver,
+            limits: info.limits,
+            max_texture_dimension_2d: info.max_texture_dimension_2d,
+        }
+    }
 }
 
+/// Calls the progress callback of [`spawn_app`] at most once per [`PROGRESS_INTERVAL`]
+///
+/// Uses wall-clock time, as `elapsed` of the progress restarts on every accumulation reset.
+/// The converging frame is reported regardless, as is the latest progress held back
+/// by the interval on pause and stop.
+struct ProgressReporter {
+    callback: RefCell<Option<js_sys::Function>>,
+    last_reported: Cell<Option<web_time::Instant>>,
+    /// Serialized [`ProgressEvent`] of the latest frame that wasn't reported
+    pending: RefCell<Option<JsValue>>,
+}
+
+impl ProgressReporter {
+    fn new(callback: Option<js_sys::Function>) -> Self {
+        ProgressReporter {
+            callback: RefCell::new(callback),
+            last_reported: Cell::new(None),
+            pending: RefCell::new(None),
+        }
+    }
+
+    fn report(&self, event: JsValue, converged: bool) {
+        let now = web_time::Instant::now();
+        if !converged
+            && self
+                .last_reported
+                .get()
+                .is_some_and(|last| now < last + PROGRESS_INTERVAL)
+        {
+            *self.pending.borrow_mut() = Some(event);
+            return;
+        }
+        self.pending.take();
+        self.last_reported.set(Some(now));
+        self.call(&event);
+    }
+
+    /// Reports the progress held back by the interval, if any
+    fn flush(&self) {
+        if let Some(event) = self.pending.take() {
+            self.last_reported.set(Some(web_time::Instant::now()));
+            self.call(&event);
+        }
+    }
+
+    fn unsubscribe(&self) {
+        self.callback.take();
+        self.pending.take();
+    }
+
+    fn call(&self, event: &JsValue) {
+        // The callback may unsubscribe
+        let Some(callback) = self.callback.borrow().clone() else {
+            return;
+        };
+        if let Err(e) = callback.call1(&JsValue::NULL, event) {
+            log::error!("Progress callback failed: {e:?}");
+        }
+    }
+}
+
+impl From<raytracer::Progress> for ProgressEvent {
+    fn from(progress: raytracer::Progress) -> Self {
+        ProgressEvent {
+            samples: progress.samples,
+            width: progress.width,
+            height: progress.height,
+            elapsed_ms: progress.elapsed.as_secs_f64() * 1e3,
+        }
+    }
+}
+
+/// Starts rendering into the canvas, resolves once the GPU is initialized
+///
+/// Rejects with an `Error` carrying a `kind` property (see [`raytracer::InitErrorKind`])
+/// if initialization fails, `bad_query_param` or `invalid_args` for unusable arguments,
+/// `scene` if the scene couldn't be loaded, `event_loop` if the event loop couldn't be started,
+/// `bad_worker_message` if the worker doesn't understand the page and `worker_failed`
+/// if the worker script fails. Query string parameters fill in whatever `args` leaves
+/// `undefined`, so pages may pass only what they compute themselves, or `undefined`
+/// for the query alone.
+/// `on_progress` is called with `{ samples, width, height, elapsedMs }` at most every 500 ms,
+/// except for the frame that reaches `max_samples` and the latest progress on pause or stop.
+///
+/// With `args.worker` set the canvas is handed over to a worker when the browser supports
+/// `OffscreenCanvas`, in that mode the rendering resolution doesn't follow the canvas size
+/// and camera controls are unavailable.
+/// Rendering pauses while the page or the canvas is hidden, unless `args.auto_pause` is false.
+///
+/// Calling it again for another canvas starts an independent app, log messages of
+/// main thread apps are prefixed with their canvas' `id`.
 #[wasm_bindgen]
-pub fn spawn_app(canvas: web_sys::HtmlCanvasElement, args: JsValue) -> Result<(), JsValue> {
-    let args: Args = if args.is_undefined() {
-        let query = query_string();
-        serde_urlencoded::from_str(&query).expect("Parsing query string")
+pub async fn spawn_app(
+    canvas: web_sys::HtmlCanvasElement,
+    args: JsValue,
+    on_progress: Option<js_sys::Function>,
+) -> Result<AppHandle, JsValue> {
+    let mut args: Args = if args.is_undefined() || args.is_null() {
+        parse_query(&query_string())?
+    } else {
+        args_over_query(&args)?
+    };
+    if args.dpr.is_some_and(|dpr| dpr.is_nan() || dpr <= 0.0) {
+        return Err(ErrorKind::InvalidArgs.error("`dpr` must be positive"));
+    }
+    // The surface follows the display, render at the requested ratio and stretch onto it
+    if let Some(dpr) = args.dpr {
+        args.resolution_scale *= (dpr / web_sys::window().unwrap().device_pixel_ratio()) as f32;
+    }
+    let world = match &args.scene {
+        Some(url) => fetch_scene(url).await?,
+        None => raytracer::World::default(),
+    };
+
+    let progress = Rc::new(ProgressReporter::new(on_progress));
+    let backend = match args.worker.clone() {
+        Some(url) if worker::is_supported(&canvas) => Backend::Worker(
+            worker::spawn(canvas.clone(), &url, args.clone(), &world, &progress).await?,
+        ),
+        worker => {
+            if worker.is_some() {
+                log::warn!("Rendering in a worker is unsupported, falling back to the main thread");
+            }
+            let app = spawn_on_main_thread(canvas.clone(), args.clone(), world, &progress)?;
+            app.initialized().await.map_err(init_error_to_js)?;
+            Backend::MainThread(app)
+        }
+    };
+
+    let controls = Rc::new(Controls {
+        backend,
+        paused: Cell::new(false),
+        hidden: Cell::new(false),
+        tonemapping: Cell::new(Tonemapping::new(&args)),
+        progress,
+    });
+    let auto_pause = if args.auto_pause {
+        Some(visibility::AutoPause::new(&canvas, &controls)?)
     } else {
-        serde_wasm_bindgen::from_value(args).map_err(|e| JsError::new(&format!("{:?}", e)))?
+        None
     };
+    Ok(AppHandle {
+        controls,
+        _auto_pause: auto_pause,
+    })
+}
 
-    let event_loop = EventLoop::with_user_event()
-        .build()
-        .expect("failed to build an event loop");
-    let app = App::new(&event_loop, args.into(), raytracer::PlatformArgs { canvas });
-    event_loop.spawn_app(app);
-    Ok(())
+/// Overrides parameters of the query string with the defined properties of `args`
+fn args_over_query(args: &JsValue) -> Result<Args, JsValue> {
+    if !args.is_object() {
+        return Err(ErrorKind::InvalidArgs.error("`args` must be an object"));
+    }
+    let query: Args = parse_query(&query_string())?;
+    // Seeds don't fit into numbers
+    let serializer =
+        serde_wasm_bindgen::Serializer::new().serialize_large_number_types_as_bigints(true);
+    let merged = query
+        .serialize(&serializer)
+        .expect("Serializing query args");
+    for entry in js_sys::Object::entries(args.unchecked_ref()) {
+        let entry: js_sys::Array = entry.unchecked_into();
+        let value = entry.get(1);
+        if !value.is_undefined() {
+            js_sys::Reflect::set(&merged, &entry.get(0), &value)?;
+        }
+    }
+    serde_wasm_bindgen::from_value(merged)
+        .map_err(|e| ErrorKind::InvalidArgs.error(&format!("Invalid `args`: {e}")))
+}
+
+fn spawn_on_main_thread(
+    canvas: web_sys::HtmlCanvasElement,
+    args: Args,
+    world: raytracer::World,
+    progress: &Rc<ProgressReporter>,
+) -> Result<raytracer::AppHandle, JsValue> {
+    let group = app_group()?;
+    let label = canvas.id();
+    let mut app = group.create_app(args.into(), world, raytracer::PlatformArgs { canvas });
+    let handle = app.handle();
+    let id = app.id().unwrap();
+    logger::set_label(
+        id,
+        if label.is_empty() {
+            id.to_string()
+        } else {
+            label
+        },
+    );
+
+    let reporter = Rc::clone(progress);
+    app.set_progress_hook(move |progress, converged| {
+        let event = serde_wasm_bindgen::to_value(&ProgressEvent::from(progress))
+            .expect("Serializing progress event");
+        reporter.report(event, converged);
+    });
+
+    group.add(app);
+    Ok(handle)
+}
+
+async fn fetch_scene(url: &str) -> Result<raytracer::World, JsValue> {
+    let error = |e: &dyn std::fmt::Display| {
+        ErrorKind::Scene.error(&format!("Failed to load scene `{url}`: {e}"))
+    };
+    let response: web_sys::Response =
+        JsFuture::from(web_sys::window().unwrap().fetch_with_str(url))
+            .await
+            .map_err(|e| error(&describe_js_error(&e)))?
+            .unchecked_into();
+    if !response.ok() {
+        return Err(error(&format_args!(
+            "HTTP {} {}",
+            response.status(),
+            response.status_text()
+        )));
+    }
+    let text = JsFuture::from(response.text().map_err(|e| error(&describe_js_error(&e)))?)
+        .await
+        .map_err(|e| error(&describe_js_error(&e)))?
+        .as_string()
+        .unwrap_or_default();
+    // Ignore the query and the fragment when looking at the extension
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    raytracer::World::parse(&text, raytracer::SceneFormat::from_path(path)).map_err(|e| error(&e))
+}
+
+fn describe_js_error(e: &JsValue) -> String {
+    match e.dyn_ref::<js_sys::Error>() {
+        Some(e) => e.message().into(),
+        None => format!("{e:?}"),
+    }
+}
+
+fn init_error_to_js(e: raytracer::InitError) -> JsValue {
+    error_with_kind(&e.message, e.kind.as_str())
+}
+
+fn error_with_kind(message: &str, kind: &str) -> JsValue {
+    let error = js_sys::Error::new(message);
+    js_sys::Reflect::set(&error, &"kind".into(), &kind.into()).expect("Setting error kind");
+    error.into()
+}
+
+/// Parses the query string, unknown parameters are ignored
+fn parse_query<T: DeserializeOwned>(query: &str) -> Result<T, JsValue> {
+    serde_urlencoded::from_str(query).map_err(|e| {
+        // Parse parameters one by one to name the offending one
+        let culprit = query
+            .split('&')
+            .find(|pair| serde_urlencoded::from_str::<T>(pair).is_err())
+            .map(|pair| pair.split_once('=').map_or(pair, |(key, _)| key));
+        match culprit {
+            Some(key) => {
+                ErrorKind::BadQueryParam.error(&format!("Invalid query parameter `{key}`: {e}"))
+            }
+            None => ErrorKind::BadQueryParam.error(&format!("Invalid query string: {e}")),
+        }
+    })
 }
 
 fn query_string() -> String {
-    let query = web_sys::window().unwrap().location().search().unwrap();
+    // Workers have no window and their location is the script's one
+    let Some(window) = web_sys::window() else {
+        return String::new();
+    };
+    let query = window.location().search().unwrap();
     Some(query.as_str())
         .filter(|q| q.is_empty())
         .or_else(|| query.strip_prefix('?'))
         .map(|s| s.to_owned())
         .unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::{wasm_bindgen_test, wasm_bindgen_test_configure};
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn kind(error: &JsValue) -> Option<String> {
+        js_sys::Reflect::get(error, &"kind".into())
+            .ok()?
+            .as_string()
+    }
+
+    fn canvas() -> web_sys::HtmlCanvasElement {
+        web_sys::window()
+            .unwrap()
+            .document()
+            .unwrap()
+            .create_element("canvas")
+            .unwrap()
+            .unchecked_into()
+    }
+
+    async fn spawn_error(args: JsValue) -> JsValue {
+        match spawn_app(canvas(), args, None).await {
+            Ok(_) => panic!("spawn_app succeeded"),
+            Err(e) => e,
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn malformed_query() {
+        let error = parse_query::<Args>("seed=1&max_samples=many").unwrap_err();
+        assert_eq!(kind(&error).as_deref(), Some("bad_query_param"));
+    }
+
+    #[wasm_bindgen_test]
+    async fn non_object_args() {
+        let error = spawn_error(JsValue::from(42)).await;
+        assert_eq!(kind(&error).as_deref(), Some("invalid_args"));
+    }
+
+    #[wasm_bindgen_test]
+    async fn missing_scene() {
+        let args = js_sys::Object::new();
+        js_sys::Reflect::set(&args, &"scene".into(), &"/missing-scene.toml".into()).unwrap();
+        let error = spawn_error(args.into()).await;
+        assert_eq!(kind(&error).as_deref(), Some("scene"));
+    }
+}
@@ -0,0 +1,61 @@
+use raytracer::AppId;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Console logger prefixing messages with the app they come from
+struct Logger;
+
+static LOGGER: Logger = Logger;
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static LABELS: RefCell<HashMap<AppId, String>> = RefCell::default();
+}
+
+/// Returns `Ok(false)` if it's already installed, fails if another logger is
+pub(crate) fn init(level: log::Level) -> Result<bool, log::SetLoggerError> {
+    if INSTALLED.load(Ordering::Relaxed) {
+        return Ok(false);
+    }
+    log::set_logger(&LOGGER)?;
+    log::set_max_level(level.to_level_filter());
+    INSTALLED.store(true, Ordering::Relaxed);
+    Ok(true)
+}
+
+/// Names the app in its messages instead of its [`AppId`]
+pub(crate) fn set_label(app: AppId, label: String) {
+    LABELS.with_borrow_mut(|labels| labels.insert(app, label));
+}
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let Some(app) = AppId::current() else {
+            return console_log::log(record);
+        };
+        let label = LABELS
+            .with_borrow(|labels| labels.get(&app).cloned())
+            .unwrap_or_else(|| app.to_string());
+        console_log::log(
+            &log::Record::builder()
+                .metadata(record.metadata().clone())
+                .module_path(record.module_path())
+                .file(record.file())
+                .line(record.line())
+                .args(format_args!("[{label}] {}", record.args()))
+                .build(),
+        );
+    }
+
+    fn flush(&self) {}
+}
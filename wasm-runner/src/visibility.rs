@@ -0,0 +1,98 @@
+use crate::Controls;
+use std::{cell::Cell, rc::Rc};
+use wasm_bindgen::prelude::*;
+
+/// Pauses the app while the page is hidden or the canvas is scrolled out of view
+///
+/// Listeners are removed on drop, resuming the app if it was hidden.
+pub(crate) struct AutoPause {
+    controls: Rc<Controls>,
+    document: web_sys::Document,
+    on_visibility_change: Closure<dyn FnMut()>,
+    observer: web_sys::IntersectionObserver,
+    _on_intersection: Closure<dyn FnMut(js_sys::Array)>,
+}
+
+#[derive(Default)]
+struct Visibility {
+    page_hidden: Cell<bool>,
+    out_of_view: Cell<bool>,
+}
+
+impl Visibility {
+    fn apply(&self, controls: &Controls) {
+        let hidden = self.page_hidden.get() || self.out_of_view.get();
+        if controls.hidden.replace(hidden) != hidden {
+            log::debug!(
+                "Canvas became {}",
+                if hidden { "hidden" } else { "visible" }
+            );
+            controls.update_pause();
+        }
+    }
+}
+
+impl AutoPause {
+    pub(crate) fn new(
+        canvas: &web_sys::HtmlCanvasElement,
+        controls: &Rc<Controls>,
+    ) -> Result<Self, JsValue> {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let visibility = Rc::new(Visibility::default());
+
+        let on_visibility_change = Closure::<dyn FnMut()>::new({
+            let document = document.clone();
+            let visibility = Rc::clone(&visibility);
+            let controls = Rc::clone(controls);
+            move || {
+                visibility.page_hidden.set(document.hidden());
+                visibility.apply(&controls);
+            }
+        });
+        document.add_event_listener_with_callback(
+            "visibilitychange",
+            on_visibility_change.as_ref().unchecked_ref(),
+        )?;
+
+        let on_intersection = Closure::<dyn FnMut(js_sys::Array)>::new({
+            let visibility = Rc::clone(&visibility);
+            let controls = Rc::clone(controls);
+            move |entries: js_sys::Array| {
+                // Only the latest state of the single observed canvas matters
+                let Some(entry) = entries.iter().last() else {
+                    return;
+                };
+                let entry: web_sys::IntersectionObserverEntry = entry.unchecked_into();
+                visibility.out_of_view.set(!entry.is_intersecting());
+                visibility.apply(&controls);
+            }
+        });
+        let observer =
+            web_sys::IntersectionObserver::new(on_intersection.as_ref().unchecked_ref())?;
+        observer.observe(canvas);
+
+        visibility.page_hidden.set(document.hidden());
+        visibility.apply(controls);
+
+        Ok(AutoPause {
+            controls: Rc::clone(controls),
+            document,
+            on_visibility_change,
+            observer,
+            _on_intersection: on_intersection,
+        })
+    }
+}
+
+impl Drop for AutoPause {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+        let _ = self.document.remove_event_listener_with_callback(
+            "visibilitychange",
+            self.on_visibility_change.as_ref().unchecked_ref(),
+        );
+        if self.controls.hidden.replace(false) {
+            self.controls.update_pause();
+        }
+    }
+}
@@ -0,0 +1,539 @@
+//! Rendering off the main thread, the worker side is started by `worker.js`
+//!
+//! Messages are plain objects with a `type` property. The main thread sends `init`
+//! with the transferred canvas, `pause`, `resume`, `set` and `capture` with a `format`,
+//! the worker answers with `ready` carrying `backendInfo` or `error` with a `message` and
+//! a `kind`, `progress` of every frame with a `converged` flag and `capture` carrying
+//! the encoded `image` or an `error`.
+
+use crate::{
+    error_with_kind, Args, BackendInfoEvent, CaptureFormat, ErrorKind, ProgressEvent,
+    ProgressReporter, Tonemapping,
+};
+use serde::Serialize;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+/// Whether the browser can render into the canvas from a worker
+pub(crate) fn is_supported(canvas: &web_sys::HtmlCanvasElement) -> bool {
+    let has = |target: &JsValue, property: &str| {
+        js_sys::Reflect::has(target, &property.into()).unwrap_or(false)
+    };
+    let global = js_sys::global();
+    // WebGPU in workers can't be detected from here, assume it's there along with the window's
+    let gpu = cfg!(feature = "webgl")
+        || web_sys::window().is_some_and(|window| has(&window.navigator(), "gpu"));
+    has(&global, "Worker")
+        && has(&global, "OffscreenCanvas")
+        && has(canvas, "transferControlToOffscreen")
+        && gpu
+}
+
+/// Hands the canvas over to a new worker, resolves once it has initialized the GPU
+pub(crate) async fn spawn(
+    canvas: web_sys::HtmlCanvasElement,
+    url: &str,
+    mut args: Args,
+    world: &raytracer::World,
+    progress: &Rc<ProgressReporter>,
+) -> Result<WorkerHandle, JsValue> {
+    [args.width, args.height] = physical_size(&canvas, &args)?;
+
+    let worker = web_sys::Worker::new_with_options(
+        url,
+        web_sys::WorkerOptions::new().type_(web_sys::WorkerType::Module),
+    )?;
+    let handle = WorkerHandle {
+        worker,
+        status: <_>::default(),
+        handlers: RefCell::new(None),
+    };
+    let initialized = js_sys::Promise::new(&mut |resolve, reject| {
+        *handle.status.init.borrow_mut() = Some((resolve, reject));
+    });
+
+    let onmessage = MessageHandler::new({
+        let status = Rc::clone(&handle.status);
+        let progress = Rc::clone(progress);
+        move |event: web_sys::MessageEvent| status.handle_message(event.data(), &progress)
+    });
+    let onerror = Closure::<dyn FnMut(web_sys::ErrorEvent)>::new({
+        let status = Rc::clone(&handle.status);
+        move |event: web_sys::ErrorEvent| {
+            let message = format!("Worker failed: {}", event.message());
+            log::error!("{message}");
+            status.finish_init(Err(ErrorKind::WorkerFailed.error(&message)));
+        }
+    });
+    handle
+        .worker
+        .set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+    handle
+        .worker
+        .set_onerror(Some(onerror.as_ref().unchecked_ref()));
+    *handle.handlers.borrow_mut() = Some(Handlers {
+        _onmessage: onmessage,
+        _onerror: onerror,
+    });
+
+    let offscreen = canvas.transfer_control_to_offscreen()?;
+    // Seeds don't fit into numbers
+    let serializer =
+        serde_wasm_bindgen::Serializer::new().serialize_large_number_types_as_bigints(true);
+    let init = message(
+        "init",
+        &[
+            ("canvas", offscreen.clone().into()),
+            ("args", args.serialize(&serializer)?),
+            ("world", world.serialize(&serializer)?),
+        ],
+    );
+    handle
+        .worker
+        .post_message_with_transfer(&init, &js_sys::Array::of1(&offscreen))?;
+
+    if let Err(e) = JsFuture::from(initialized).await {
+        handle.stop();
+        return Err(e);
+    }
+    Ok(handle)
+}
+
+/// Resolves the canvas size the same way the main thread window does
+fn physical_size(canvas: &web_sys::HtmlCanvasElement, args: &Args) -> Result<[u32; 2], JsValue> {
+    let scale_factor = web_sys::window().unwrap().device_pixel_ratio();
+    let physical = |side: f64| ((side * scale_factor).round() as u32).max(1);
+    let [width, height] = match [args.width, args.height] {
+        [0, 0] => {
+            return Ok(
+                [canvas.client_width(), canvas.client_height()].map(|side| physical(side.into()))
+            )
+        }
+        [side, 0] | [0, side] => [side; 2],
+        size => size,
+    };
+    let style = canvas.style();
+    style.set_property("width", &format!("{width}px"))?;
+    style.set_property("height", &format!("{height}px"))?;
+    Ok([width, height].map(|side| physical(side.into())))
+}
+
+/// Commands the worker over `postMessage`
+///
+/// Dropping the handle leaves the worker running, its event handlers are leaked then.
+pub(crate) struct WorkerHandle {
+    worker: web_sys::Worker,
+    status: Rc<WorkerStatus>,
+    /// Dropped on stop
+    handlers: RefCell<Option<Handlers>>,
+}
+
+type MessageHandler = Closure<dyn FnMut(web_sys::MessageEvent)>;
+
+struct Handlers {
+    _onmessage: MessageHandler,
+    _onerror: Closure<dyn FnMut(web_sys::ErrorEvent)>,
+}
+
+#[derive(Default)]
+struct WorkerStatus {
+    stopped: Cell<bool>,
+    /// Sent along with `ready`
+    backend_info: RefCell<JsValue>,
+    /// Resolve and reject functions of the initialization promise
+    init: RefCell<Option<(js_sys::Function, js_sys::Function)>>,
+    next_capture_id: Cell<u32>,
+    /// Resolve and reject functions of pending captures
+    captures: RefCell<HashMap<u32, (js_sys::Function, js_sys::Function)>>,
+}
+
+impl WorkerHandle {
+    pub(crate) fn stop(&self) {
+        if self.status.stopped.replace(true) {
+            return;
+        }
+        self.worker.terminate();
+        self.worker.set_onmessage(None);
+        self.worker.set_onerror(None);
+        self.handlers.take();
+        self.status.finish_init(Err(error_with_kind(
+            "app was closed before initialization has finished",
+            raytracer::InitErrorKind::Closed.as_str(),
+        )));
+        for (_, (_, reject)) in self.status.captures.take() {
+            let _ = reject.call1(&JsValue::NULL, &JsError::new("App is not running").into());
+        }
+    }
+
+    pub(crate) fn pause(&self) {
+        self.set_paused(true);
+    }
+
+    pub(crate) fn resume(&self) {
+        self.set_paused(false);
+    }
+
+    fn set_paused(&self, paused: bool) {
+        if self.status.stopped.get() {
+            return;
+        }
+        self.post(&message(if paused { "pause" } else { "resume" }, &[]));
+    }
+
+    pub(crate) fn set_param(&self, param: raytracer::Param) {
+        if self.status.stopped.get() {
+            return;
+        }
+        let param = serde_wasm_bindgen::to_value(&param).expect("Serializing a parameter");
+        self.post(&message("set", &[("param", param)]));
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        !self.status.stopped.get()
+    }
+
+    pub(crate) fn backend_info(&self) -> JsValue {
+        self.status.backend_info.borrow().clone()
+    }
+
+    pub(crate) fn capture(&self, format: CaptureFormat) -> js_sys::Promise {
+        if self.status.stopped.get() {
+            return js_sys::Promise::reject(&JsError::new("App is not running").into());
+        }
+        let id = self.status.next_capture_id.get();
+        self.status.next_capture_id.set(id.wrapping_add(1));
+        let promise = js_sys::Promise::new(&mut |resolve, reject| {
+            self.status
+                .captures
+                .borrow_mut()
+                .insert(id, (resolve, reject));
+        });
+        self.post(&message(
+            "capture",
+            &[("id", id.into()), ("format", format.as_str().into())],
+        ));
+        promise
+    }
+
+    fn post(&self, message: &JsValue) {
+        if let Err(e) = self.worker.post_message(message) {
+            log::error!("Failed to message the worker: {e:?}");
+        }
+    }
+}
+
+impl Drop for WorkerHandle {
+    fn drop(&mut self) {
+        // The worker keeps messaging the page
+        if let Some(handlers) = self.handlers.take() {
+            std::mem::forget(handlers);
+        }
+    }
+}
+
+impl WorkerStatus {
+    fn handle_message(&self, data: JsValue, progress: &ProgressReporter) {
+        let get = |key: &str| js_sys::Reflect::get(&data, &key.into()).unwrap_or_default();
+        match get("type").as_string().as_deref() {
+            Some("ready") => {
+                *self.backend_info.borrow_mut() = get("backendInfo");
+                self.finish_init(Ok(()));
+            }
+            Some("error") => {
+                let message = get("message").as_string().unwrap_or_default();
+                let kind = get("kind").as_string().unwrap_or_default();
+                if self.init.borrow().is_some() {
+                    self.finish_init(Err(error_with_kind(&message, &kind)));
+                } else {
+                    log::error!("Worker error ({kind}): {message}");
+                }
+            }
+            Some("progress") => progress.report(get("progress"), get("converged").is_truthy()),
+            Some("capture") => {
+                let id = get("id").as_f64().unwrap_or_default() as u32;
+                let pending = self.captures.borrow_mut().remove(&id);
+                let Some((resolve, reject)) = pending else {
+                    return;
+                };
+                let _ = match (get("image"), get("error")) {
+                    (_, error) if !error.is_undefined() => reject.call1(&JsValue::NULL, &error),
+                    (image, _) if image.is_undefined() => {
+                        reject.call1(&JsValue::NULL, &JsError::new("App is not running").into())
+                    }
+                    (image, _) => resolve.call1(&JsValue::NULL, &image),
+                };
+            }
+            _ => log::warn!("Unexpected message from the worker: {data:?}"),
+        }
+    }
+
+    /// Settles the initialization promise, later results are ignored
+    fn finish_init(&self, result: Result<(), JsValue>) {
+        let Some((resolve, reject)) = self.init.take() else {
+            return;
+        };
+        let _ = match result {
+            Ok(()) => resolve.call0(&JsValue::NULL),
+            Err(e) => reject.call1(&JsValue::NULL, &e),
+        };
+    }
+}
+
+thread_local! {
+    /// Handler of main thread messages, lives until the worker is terminated
+    static ONMESSAGE: RefCell<Option<MessageHandler>> =
+        const { RefCell::new(None) };
+}
+
+/// Entry point of `worker.js`, `queued` holds messages received while the module was loading
+#[wasm_bindgen]
+pub fn worker_main(queued: js_sys::Array) {
+    let worker = Rc::new(Worker {
+        scope: js_sys::global().unchecked_into(),
+        app: RefCell::new(None),
+        tonemapping: Cell::new(Tonemapping {
+            exposure: 1.0,
+            tonemap: raytracer::Tonemap::None,
+        }),
+        paused: Cell::new(false),
+        frame_interval_ms: Cell::new(0.0),
+        last_frame: Cell::new(None),
+    });
+
+    let onmessage = MessageHandler::new({
+        let worker = Rc::clone(&worker);
+        move |event: web_sys::MessageEvent| worker.handle_message(event.data())
+    });
+    worker
+        .scope
+        .set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+    ONMESSAGE.set(Some(onmessage));
+
+    for event in queued.iter() {
+        worker.handle_message(event.unchecked_into::<web_sys::MessageEvent>().data());
+    }
+}
+
+/// Worker side of the app, redraws are driven by `requestAnimationFrame`
+struct Worker {
+    scope: web_sys::DedicatedWorkerGlobalScope,
+    app: RefCell<Option<raytracer::OffscreenApp>>,
+    /// Mirrors the app's exposure for PNG captures
+    tonemapping: Cell<Tonemapping>,
+    paused: Cell<bool>,
+    /// Minimal time between accumulated frames, zero if unlimited
+    frame_interval_ms: Cell<f64>,
+    /// Animation frame timestamp of the latest accumulated frame
+    last_frame: Cell<Option<f64>>,
+}
+
+/// Animation frame timestamps jitter, frames slightly early are accepted to keep the rate
+const FRAME_SLACK_MS: f64 = 2.0;
+
+/// Same rate limit as on the main thread, zero if unthrottled
+fn frame_interval_ms(target_fps: f32) -> f64 {
+    raytracer::frame_interval(target_fps).map_or(0.0, |interval| interval.as_secs_f64() * 1e3)
+}
+
+impl Worker {
+    fn handle_message(self: &Rc<Self>, data: JsValue) {
+        let get = |key: &str| js_sys::Reflect::get(&data, &key.into()).unwrap_or_default();
+        match get("type").as_string().as_deref() {
+            Some("init") => {
+                let args: Args = match serde_wasm_bindgen::from_value(get("args")) {
+                    Ok(args) => args,
+                    Err(e) => {
+                        let message = format!("Invalid worker args: {e}");
+                        return self.post_error(&message, ErrorKind::InvalidArgs.as_str());
+                    }
+                };
+                let world: raytracer::World = match serde_wasm_bindgen::from_value(get("world")) {
+                    Ok(world) => world,
+                    Err(e) => {
+                        let message = format!("Invalid worker scene: {e}");
+                        return self.post_error(&message, ErrorKind::Scene.as_str());
+                    }
+                };
+                let canvas = get("canvas").unchecked_into();
+                let worker = Rc::clone(self);
+                wasm_bindgen_futures::spawn_local(async move {
+                    worker.init(canvas, args, world).await;
+                });
+            }
+            Some("pause") => self.paused.set(true),
+            Some("resume") => self.paused.set(false),
+            Some("set") => {
+                let param = match serde_wasm_bindgen::from_value(get("param")) {
+                    Ok(param) => param,
+                    Err(e) => {
+                        let message = format!("Invalid worker parameter: {e}");
+                        return self.post_error(&message, ErrorKind::BadWorkerMessage.as_str());
+                    }
+                };
+                let mut tonemapping = self.tonemapping.get();
+                tonemapping.update(param);
+                self.tonemapping.set(tonemapping);
+                if let raytracer::Param::TargetFps(fps) = param {
+                    self.frame_interval_ms.set(frame_interval_ms(fps));
+                } else if let Some(app) = self.app.borrow_mut().as_mut() {
+                    app.set_param(param);
+                    // The frame loop stops redrawing once converged
+                    if app.is_converged() && !self.paused.get() {
+                        app.redraw();
+                    }
+                }
+            }
+            Some("capture") => {
+                let id = get("id");
+                let format = get("format").as_string();
+                let Some(format) = format.as_deref().and_then(CaptureFormat::from_str) else {
+                    let error = error_with_kind(
+                        &format!("Invalid capture format {format:?}"),
+                        ErrorKind::BadWorkerMessage.as_str(),
+                    );
+                    return self.post(&message("capture", &[("id", id), ("error", error)]));
+                };
+                let capture = self.app.borrow().as_ref().map(|app| app.capture());
+                let tonemapping = self.tonemapping.get();
+                let worker = Rc::clone(self);
+                wasm_bindgen_futures::spawn_local(async move {
+                    let image = match capture {
+                        Some(capture) => capture.await,
+                        None => None,
+                    };
+                    let mut fields = vec![("id", id)];
+                    let transfer = js_sys::Array::new();
+                    match image.map(|image| format.encode(image, tonemapping)) {
+                        Some(Ok(image)) => {
+                            // Move the pixels instead of copying them
+                            if format == CaptureFormat::Raw {
+                                let data = js_sys::Reflect::get(&image, &"data".into()).unwrap();
+                                transfer
+                                    .push(&data.unchecked_into::<js_sys::Float32Array>().buffer());
+                            }
+                            fields.push(("image", image));
+                        }
+                        Some(Err(e)) => log::error!("Failed to encode a capture: {e:?}"),
+                        None => (),
+                    }
+                    worker.post_with_transfer(&message("capture", &fields), &transfer);
+                });
+            }
+            _ => log::warn!("Unexpected message from the main thread: {data:?}"),
+        }
+    }
+
+    async fn init(
+        self: Rc<Self>,
+        canvas: web_sys::OffscreenCanvas,
+        args: Args,
+        world: raytracer::World,
+    ) {
+        self.frame_interval_ms
+            .set(frame_interval_ms(args.target_fps));
+        self.tonemapping.set(Tonemapping::new(&args));
+        match raytracer::OffscreenApp::new(canvas, args.into(), &world).await {
+            Ok(app) => {
+                let backend_info = serde_wasm_bindgen::to_value(&BackendInfoEvent::from(
+                    app.backend_info().clone(),
+                ))
+                .expect("Serializing backend info");
+                *self.app.borrow_mut() = Some(app);
+                self.post(&message("ready", &[("backendInfo", backend_info)]));
+                self.start_frame_loop();
+            }
+            Err(e) => {
+                log::error!("Failed to initialize: {e}");
+                self.post_error(&e.message, e.kind.as_str());
+            }
+        }
+    }
+
+    fn start_frame_loop(self: &Rc<Self>) {
+        let callback = Rc::new(RefCell::new(None::<Closure<dyn FnMut(f64)>>));
+        *callback.borrow_mut() = Some(Closure::new({
+            let worker = Rc::clone(self);
+            let callback = Rc::clone(&callback);
+            move |time| {
+                worker.frame(time);
+                worker.request_animation_frame(callback.borrow().as_ref().unwrap());
+            }
+        }));
+        self.request_animation_frame(callback.borrow().as_ref().unwrap());
+    }
+
+    fn request_animation_frame(&self, callback: &Closure<dyn FnMut(f64)>) {
+        self.scope
+            .request_animation_frame(callback.as_ref().unchecked_ref())
+            .expect("Requesting an animation frame");
+    }
+
+    /// Accumulates a frame unless paused, converged or throttled by `target_fps`
+    fn frame(&self, time: f64) {
+        if self.paused.get() {
+            return;
+        }
+        let mut app = self.app.borrow_mut();
+        let Some(app) = app.as_mut() else {
+            return;
+        };
+        if app.is_converged() {
+            return;
+        }
+        let interval = self.frame_interval_ms.get();
+        if self
+            .last_frame
+            .get()
+            .is_some_and(|last| time - last < interval - FRAME_SLACK_MS)
+        {
+            return;
+        }
+        self.last_frame.set(Some(time));
+        app.redraw();
+
+        // Every frame is sent, the main thread keeps the latest one for pause and stop
+        let event = serde_wasm_bindgen::to_value(&ProgressEvent::from(app.progress()))
+            .expect("Serializing progress event");
+        let converged = app.is_converged().into();
+        self.post(&message(
+            "progress",
+            &[("progress", event), ("converged", converged)],
+        ));
+    }
+
+    fn post(&self, message: &JsValue) {
+        self.post_with_transfer(message, &js_sys::Array::new());
+    }
+
+    /// Rejects initialization if it's pending, otherwise the main thread logs the error
+    fn post_error(&self, text: &str, kind: &str) {
+        self.post(&message(
+            "error",
+            &[("message", text.into()), ("kind", kind.into())],
+        ));
+    }
+
+    fn post_with_transfer(&self, message: &JsValue, transfer: &js_sys::Array) {
+        if let Err(e) = self.scope.post_message_with_transfer(message, transfer) {
+            log::error!("Failed to message the main thread: {e:?}");
+        }
+    }
+}
+
+/// Builds `{ type, ...fields }`
+fn message(ty: &str, fields: &[(&str, JsValue)]) -> JsValue {
+    let message = js_sys::Object::new();
+    let ty = JsValue::from(ty);
+    for (key, value) in [("type", &ty)]
+        .into_iter()
+        .chain(fields.iter().map(|(key, value)| (*key, value)))
+    {
+        js_sys::Reflect::set(&message, &key.into(), value).expect("Building a message");
+    }
+    message.into()
+}
@@ -0,0 +1,9 @@
+// Renders off the main thread, started by `spawn_app` when `args.worker` points here
+import init, { worker_main } from './pkg/wasm_runner.js';
+
+// Messages arriving while the module loads would be lost otherwise
+const queued = [];
+self.onmessage = (event) => queued.push(event);
+
+await init();
+worker_main(queued);
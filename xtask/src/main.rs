@@ -3,6 +3,9 @@ use std::{
     process::{Command, Stdio},
 };
 
+mod optimize;
+mod serve;
+
 fn main() {
     let mut args = env::args();
 
@@ -11,6 +14,11 @@ fn main() {
 
     match subcommand.as_deref() {
         Some("build-wasm") => {
+            let mut args: Vec<String> = args.collect();
+            // Still passed to cargo, it only adds the wasm-opt step on top
+            let release = args.iter().any(|arg| arg == "--release");
+            let reference_types = take_flag(&mut args, "--reference-types");
+
             let cargo = env::var_os("CARGO");
             let cargo = cargo.as_deref().unwrap_or_else(|| "cargo".as_ref());
 
@@ -62,24 +70,56 @@ fn main() {
                 .web(true)
                 .unwrap()
                 .omit_default_module_path(false)
+                .reference_types(reference_types)
                 .generate(&dump)
                 .unwrap();
 
             // TODO: think of a better way
-            std::fs::copy(
-                workspace_root.join("index.html"),
-                workspace_root.join("deploy/index.html"),
-            )
-            .unwrap();
+            for file in ["index.html", "multi.html", "worker.js"] {
+                std::fs::copy(
+                    workspace_root.join(file),
+                    workspace_root.join("deploy").join(file),
+                )
+                .unwrap();
+            }
+
+            // Last, so that the unoptimized build is complete if this fails
+            if release {
+                for entry in std::fs::read_dir(&dump).unwrap() {
+                    let path = entry.unwrap().path();
+                    if path.to_string_lossy().ends_with("_bg.wasm") {
+                        optimize::optimize(&path);
+                    }
+                }
+            }
+        }
+        Some("serve") => {
+            let workspace_root = cargo_metadata::MetadataCommand::new()
+                .no_deps()
+                .other_options(["--offline"].map(|s| s.to_owned()))
+                .exec()
+                .unwrap()
+                .workspace_root;
+            serve::serve(args, workspace_root.join("deploy").into())
         }
         _ => print_help(),
     }
 }
 
+/// Removes all occurrences of `flag`, returns whether there were any
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let len = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != len
+}
+
 fn print_help() {
     eprintln!(
         "Tasks:
         build-wasm <cargo-build-args>       builds wasm module and runs wasm-bindgen
+            --release                       also shrinks the module with wasm-opt -Oz
+            --reference-types               emits glue using wasm reference types
+        serve [--port <port>] [--open]      serves the wasm build on localhost, port 8000 by default
         "
     )
 }
@@ -0,0 +1,66 @@
+use std::path::Path;
+#[cfg(not(feature = "wasm-opt"))]
+use std::process::Command;
+
+/// Shrinks a module in place with binaryen's `-Oz`, exits with a hint if that's impossible
+///
+/// Uses the `wasm-opt` crate if xtask is built with the feature of the same name,
+/// otherwise the `wasm-opt` binary on `PATH`.
+pub fn optimize(wasm: &Path) {
+    let before = file_size(wasm);
+    if let Err(e) = run_wasm_opt(wasm) {
+        eprintln!(
+            "error: {e}
+hint: install binaryen to get `wasm-opt` on PATH, or build xtask with `--features wasm-opt`
+      `{}` is left unoptimized",
+            wasm.display()
+        );
+        std::process::exit(1);
+    }
+    let after = file_size(wasm);
+    println!(
+        "wasm-opt: {} KiB -> {} KiB ({:.1}% smaller)",
+        before / 1024,
+        after / 1024,
+        100.0 * (1.0 - after as f64 / before as f64)
+    );
+}
+
+fn file_size(path: &Path) -> u64 {
+    std::fs::metadata(path).unwrap().len()
+}
+
+#[cfg(not(feature = "wasm-opt"))]
+fn run_wasm_opt(wasm: &Path) -> Result<(), String> {
+    // Binaryen rejects features the wasm32 target of current rustc emits unless enabled
+    let status = Command::new("wasm-opt")
+        .args([
+            "-Oz",
+            "--enable-nontrapping-float-to-int",
+            "--enable-bulk-memory",
+            "--enable-reference-types",
+            "--enable-multivalue",
+        ])
+        .arg(wasm)
+        .arg("-o")
+        .arg(wasm)
+        .status()
+        .map_err(|e| format!("can't run `wasm-opt`: {e}"))?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(format!("`wasm-opt` failed with {status}")),
+    }
+}
+
+#[cfg(feature = "wasm-opt")]
+fn run_wasm_opt(wasm: &Path) -> Result<(), String> {
+    use wasm_opt::Feature;
+    // Binaryen rejects features the wasm32 target of current rustc emits unless enabled
+    wasm_opt::OptimizationOptions::new_optimize_for_size_aggressively()
+        .enable_feature(Feature::TruncSat)
+        .enable_feature(Feature::BulkMemory)
+        .enable_feature(Feature::ReferenceTypes)
+        .enable_feature(Feature::Multivalue)
+        .run(wasm, wasm)
+        .map_err(|e| format!("wasm-opt failed: {e}"))
+}
@@ -0,0 +1,179 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::{Component, Path, PathBuf},
+    process::Command,
+};
+
+const DEFAULT_PORT: u16 = 8000;
+
+/// Serves `deploy/` for local testing, see `print_help`
+pub fn serve(mut args: impl Iterator<Item = String>, root: PathBuf) {
+    let mut port = DEFAULT_PORT;
+    let mut open = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => {
+                port = args
+                    .next()
+                    .and_then(|port| port.parse().ok())
+                    .expect("--port takes a number")
+            }
+            "--open" => open = true,
+            _ => panic!("unexpected argument `{arg}`"),
+        }
+    }
+
+    if !root.join("index.html").is_file() {
+        eprintln!(
+            "`{}` has no index.html, run `cargo xtask build-wasm` first",
+            root.display()
+        );
+        std::process::exit(1);
+    }
+
+    let listener = TcpListener::bind(("127.0.0.1", port)).unwrap();
+    let url = format!("http://127.0.0.1:{port}/");
+    println!("Serving `{}` at {url}", root.display());
+    println!("Try for example:");
+    println!("  {url}?sample_count=4&ray_depth=8");
+    println!("  {url}?tonemap=aces&exposure=1.5&resolution_scale=0.5");
+    println!("  {url}?worker=worker.js&target_fps=30");
+    println!("  {url}multi.html");
+
+    if open {
+        open_browser(&url);
+    }
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let root = root.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = respond(stream, &root) {
+                eprintln!("Failed to respond: {e}");
+            }
+        });
+    }
+}
+
+fn respond(mut stream: TcpStream, root: &Path) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Headers are irrelevant, but have to be read before responding
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    if method != "GET" && method != "HEAD" {
+        return write_response(
+            &mut stream,
+            "405 Method Not Allowed",
+            "text/plain",
+            b"",
+            true,
+        );
+    }
+    let path = target.split(['?', '#']).next().unwrap_or("/");
+    let Some(file) = resolve(root, path) else {
+        return write_response(
+            &mut stream,
+            "404 Not Found",
+            "text/plain",
+            b"not found",
+            true,
+        );
+    };
+    let body = match std::fs::read(&file) {
+        Ok(body) => body,
+        Err(_) => {
+            return write_response(
+                &mut stream,
+                "404 Not Found",
+                "text/plain",
+                b"not found",
+                true,
+            )
+        }
+    };
+    println!("{method} {target}");
+    write_response(
+        &mut stream,
+        "200 OK",
+        content_type(&file),
+        &body,
+        method == "GET",
+    )
+}
+
+/// Maps a request path into `root`, rejecting attempts to leave it
+fn resolve(root: &Path, path: &str) -> Option<PathBuf> {
+    let relative = Path::new(path.trim_start_matches('/'));
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+    let file = root.join(relative);
+    Some(match file.is_dir() {
+        true => file.join("index.html"),
+        false => file,
+    })
+}
+
+fn content_type(file: &Path) -> &'static str {
+    match file.extension().and_then(|ext| ext.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("wasm") => "application/wasm",
+        Some("json") => "application/json",
+        Some("ron" | "ts" | "txt") => "text/plain; charset=utf-8",
+        Some("css") => "text/css",
+        Some("png") => "image/png",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Cross-origin isolation headers let pages use `SharedArrayBuffer`, e.g. for WebGPU in workers
+fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+    with_body: bool,
+) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\n\
+         Content-Type: {content_type}\r\n\
+         Content-Length: {}\r\n\
+         Cross-Origin-Opener-Policy: same-origin\r\n\
+         Cross-Origin-Embedder-Policy: require-corp\r\n\
+         Cache-Control: no-cache\r\n\
+         Connection: close\r\n\r\n",
+        body.len()
+    )?;
+    if with_body {
+        stream.write_all(body)?;
+    }
+    stream.flush()
+}
+
+fn open_browser(url: &str) {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    if let Err(e) = command.arg(url).spawn() {
+        eprintln!("Couldn't open a browser, visit {url} manually: {e}");
+    }
+}